@@ -0,0 +1,49 @@
+use std::{env, path::Path};
+
+use rust_nn::data::csv::{CsvReader, MissingValues};
+use rust_nn::nn::activations::{IDENTITY, RELU, SIGMOID};
+use rust_nn::nn::metrics::accuracy;
+use rust_nn::nn::network::Network;
+use rust_nn::nn::trainer::Trainer;
+
+// Rust counterpart of test.py: trains a small classifier on an iris CSV with
+// a header, four feature columns and a `species` column. Species are one-hot
+// encoded, the default for text columns.
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Usage: {} <iris csv>", args[0]);
+        std::process::exit(2);
+    }
+    let path = &args[1];
+
+    let table = CsvReader::new()
+        .target("species")
+        .missing_values(MissingValues::Drop)
+        .read(Path::new(path));
+    println!(
+        "Loaded {} rows with features {:?} and classes {:?}",
+        table.len(),
+        table.feature_names,
+        table.categories["species"]
+    );
+
+    // The activation of a layer is applied to the transform leaving it.
+    let mut network = Network::new(
+        vec![
+            (table.inputs[0].len(), RELU),
+            (10, SIGMOID),
+            (table.targets[0].len(), IDENTITY),
+        ],
+        0.01,
+    );
+
+    let history = Trainer::new(400, 50)
+        .shuffle(0)
+        .metric("accuracy", accuracy)
+        .fit(&mut network, &table.inputs, &table.targets);
+
+    let last = history.epochs.last().unwrap();
+    println!("Loss: {:.5}", last.train_loss);
+    println!("Accuracy: {:.4}", last.value("accuracy").unwrap());
+}
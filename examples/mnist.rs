@@ -0,0 +1,125 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rust_nn::data::augmentation::{
+    Compose, GaussianNoise, RandomRotation, RandomScaling, RandomTranslation, Shape,
+};
+use rust_nn::data::dataset::InMemoryDataset;
+use rust_nn::data::loader::DataLoader;
+use rust_nn::data::mnist::Mnist;
+use rust_nn::nn::activations::{IDENTITY, RELU, SIGMOID};
+use rust_nn::nn::metrics::{accuracy, confusion_matrix};
+use rust_nn::nn::network::Network;
+use rust_nn::nn::stability::GradientClipping;
+use rust_nn::nn::trainer::Trainer;
+
+// Accepts both the raw and the gzipped files of the MNIST distribution.
+fn find(directory: &Path, name: &str) -> PathBuf {
+    let path = directory.join(name);
+    if path.exists() {
+        return path;
+    }
+    let gzipped = directory.join(format!("{}.gz", name));
+    if gzipped.exists() {
+        return gzipped;
+    }
+    panic!("Neither {} nor its .gz exist", path.display());
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        eprintln!(
+            "Usage: {} <mnist directory> [training samples] [epochs] [augment]",
+            args[0]
+        );
+        std::process::exit(2);
+    }
+
+    let directory = Path::new(&args[1]);
+    let limit: Option<usize> = args
+        .get(2)
+        .map(|n| n.parse().expect("Invalid sample count"));
+    let epochs: usize = args
+        .get(3)
+        .map_or(5, |n| n.parse().expect("Invalid epoch count"));
+    let augment = args.get(4).map(|flag| flag.as_str()) == Some("augment");
+
+    let mut train = Mnist::load(
+        &find(directory, "train-images-idx3-ubyte"),
+        &find(directory, "train-labels-idx1-ubyte"),
+    );
+    let test = Mnist::load(
+        &find(directory, "t10k-images-idx3-ubyte"),
+        &find(directory, "t10k-labels-idx1-ubyte"),
+    );
+    if let Some(limit) = limit {
+        train.truncate(limit);
+    }
+    println!(
+        "Loaded {} training and {} test images",
+        train.len(),
+        test.len()
+    );
+
+    // The activation of a layer is applied to the transform leaving it.
+    let mut network = Network::new(
+        vec![
+            (train.rows * train.columns, RELU),
+            (64, SIGMOID),
+            (Mnist::CLASSES, IDENTITY),
+        ],
+        0.05,
+    );
+    network.set_gradient_clipping(Some(GradientClipping::Norm(5.0)));
+
+    let train_targets = train.targets();
+    let test_targets = test.targets();
+    let mut trainer = Trainer::new(epochs, 32)
+        .validation(test.images.clone(), test_targets.clone())
+        .metric("accuracy", accuracy);
+
+    let dataset = InMemoryDataset::new(train.images.clone(), train_targets);
+    let mut loader = DataLoader::new(Arc::new(dataset), 32).shuffle(0);
+    if augment {
+        // Small distortions of the digits help when training on few samples.
+        let augmentations = Compose::new(Shape::new(train.columns, train.rows, 1))
+            .then(RandomRotation { max_degrees: 10.0 })
+            .then(RandomScaling { min: 0.9, max: 1.1 })
+            .then(RandomTranslation { max_fraction: 0.1 })
+            .then(GaussianNoise { stddev: 0.05 });
+        loader = loader.augment(augmentations, 0);
+    }
+    let history = trainer.fit_loader(&mut network, &mut loader);
+    for log in &history.epochs {
+        println!(
+            "epoch {}: loss {:.5}, accuracy {:.4}, test loss {:.5}, test accuracy {:.4}",
+            log.epoch,
+            log.train_loss,
+            log.value("accuracy").unwrap_or(0.0),
+            log.value("val_loss").unwrap_or(0.0),
+            log.value("val_accuracy").unwrap_or(0.0),
+        );
+    }
+
+    network.eval_mode();
+    let outputs = network.feed_forward(test.images.clone());
+    println!("Test accuracy: {:.4}", accuracy(&outputs, &test_targets));
+
+    println!("Confusion matrix (rows: true digit, columns: predicted digit)");
+    print!("     ");
+    for digit in 0..Mnist::CLASSES {
+        print!("{:>6}", digit);
+    }
+    println!();
+    for (digit, row) in confusion_matrix(&outputs, &test_targets).iter().enumerate() {
+        print!("{:>5}", digit);
+        for count in row {
+            print!("{:>6}", count);
+        }
+        println!();
+    }
+}
@@ -0,0 +1,424 @@
+use rand::{rngs::StdRng, Rng};
+
+use crate::nn::matrix::standard_normal;
+
+// Layout of a flattened image: rows of pixels with interleaved channels, as
+// produced by `ImageFolder` and `Mnist`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shape {
+    pub width: usize,
+    pub height: usize,
+    pub channels: usize,
+}
+
+impl Shape {
+    pub fn new(width: usize, height: usize, channels: usize) -> Shape {
+        Shape {
+            width,
+            height,
+            channels,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.width * self.height * self.channels
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn index(&self, x: usize, y: usize, channel: usize) -> usize {
+        (y * self.width + x) * self.channels + channel
+    }
+}
+
+// A random image transformation. All randomness comes from `rng`, so a
+// seeded generator reproduces the same augmented images.
+pub trait Augmentation: Send + Sync {
+    fn apply(&self, image: Vec<f64>, shape: Shape, rng: &mut StdRng) -> Vec<f64>;
+}
+
+// Bilinear interpolation, pixels outside the image are 0.
+fn sample(image: &[f64], shape: Shape, x: f64, y: f64, channel: usize) -> f64 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (dx, dy) = (x - x0, y - y0);
+    let pixel = |x: f64, y: f64| {
+        if x < 0.0 || y < 0.0 || x >= shape.width as f64 || y >= shape.height as f64 {
+            0.0
+        } else {
+            image[shape.index(x as usize, y as usize, channel)]
+        }
+    };
+
+    pixel(x0, y0) * (1.0 - dx) * (1.0 - dy)
+        + pixel(x0 + 1.0, y0) * dx * (1.0 - dy)
+        + pixel(x0, y0 + 1.0) * (1.0 - dx) * dy
+        + pixel(x0 + 1.0, y0 + 1.0) * dx * dy
+}
+
+// Builds every output pixel from the source position `source(x, y)`.
+fn warp(image: &[f64], shape: Shape, source: impl Fn(f64, f64) -> (f64, f64)) -> Vec<f64> {
+    let mut output = vec![0.0; shape.len()];
+    for y in 0..shape.height {
+        for x in 0..shape.width {
+            let (sx, sy) = source(x as f64, y as f64);
+            for channel in 0..shape.channels {
+                output[shape.index(x, y, channel)] = sample(image, shape, sx, sy, channel);
+            }
+        }
+    }
+    output
+}
+
+fn center(shape: Shape) -> (f64, f64) {
+    (
+        (shape.width as f64 - 1.0) / 2.0,
+        (shape.height as f64 - 1.0) / 2.0,
+    )
+}
+
+// Pads the image with zeros on every side and crops a random window of the
+// original size.
+pub struct RandomCrop {
+    pub padding: usize,
+}
+
+impl Augmentation for RandomCrop {
+    fn apply(&self, image: Vec<f64>, shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        let padding = self.padding as i64;
+        let dx = rng.gen_range(-padding..=padding) as f64;
+        let dy = rng.gen_range(-padding..=padding) as f64;
+        warp(&image, shape, |x, y| (x + dx, y + dy))
+    }
+}
+
+pub struct RandomFlip {
+    pub horizontal: bool,
+    pub probability: f64,
+}
+
+impl RandomFlip {
+    pub fn horizontal(probability: f64) -> RandomFlip {
+        RandomFlip {
+            horizontal: true,
+            probability,
+        }
+    }
+
+    pub fn vertical(probability: f64) -> RandomFlip {
+        RandomFlip {
+            horizontal: false,
+            probability,
+        }
+    }
+}
+
+impl Augmentation for RandomFlip {
+    fn apply(&self, image: Vec<f64>, shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        if rng.gen::<f64>() >= self.probability {
+            return image;
+        }
+        let (right, bottom) = ((shape.width - 1) as f64, (shape.height - 1) as f64);
+        if self.horizontal {
+            warp(&image, shape, |x, y| (right - x, y))
+        } else {
+            warp(&image, shape, |x, y| (x, bottom - y))
+        }
+    }
+}
+
+// Rotates around the center by an angle in [-max_degrees, max_degrees].
+pub struct RandomRotation {
+    pub max_degrees: f64,
+}
+
+impl Augmentation for RandomRotation {
+    fn apply(&self, image: Vec<f64>, shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        let angle = rng
+            .gen_range(-self.max_degrees..=self.max_degrees)
+            .to_radians();
+        let (sin, cos) = angle.sin_cos();
+        let (cx, cy) = center(shape);
+        warp(&image, shape, |x, y| {
+            let (x, y) = (x - cx, y - cy);
+            (cos * x + sin * y + cx, -sin * x + cos * y + cy)
+        })
+    }
+}
+
+// Shifts by up to `max_fraction` of the width and height.
+pub struct RandomTranslation {
+    pub max_fraction: f64,
+}
+
+impl Augmentation for RandomTranslation {
+    fn apply(&self, image: Vec<f64>, shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        let dx = rng.gen_range(-self.max_fraction..=self.max_fraction) * shape.width as f64;
+        let dy = rng.gen_range(-self.max_fraction..=self.max_fraction) * shape.height as f64;
+        warp(&image, shape, |x, y| (x - dx, y - dy))
+    }
+}
+
+// Zooms around the center by a factor in [min, max].
+pub struct RandomScaling {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Augmentation for RandomScaling {
+    fn apply(&self, image: Vec<f64>, shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        let factor = rng.gen_range(self.min..=self.max);
+        let (cx, cy) = center(shape);
+        warp(&image, shape, |x, y| {
+            ((x - cx) / factor + cx, (y - cy) / factor + cy)
+        })
+    }
+}
+
+// Adds an offset in [-brightness, brightness] and scales the distance to the
+// image mean by a factor in [1 - contrast, 1 + contrast], clamped to [0, 1].
+pub struct ColorJitter {
+    pub brightness: f64,
+    pub contrast: f64,
+}
+
+impl Augmentation for ColorJitter {
+    fn apply(&self, image: Vec<f64>, _shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        let offset = rng.gen_range(-self.brightness..=self.brightness);
+        let factor = rng.gen_range(1.0 - self.contrast..=1.0 + self.contrast);
+        let mean = image.iter().sum::<f64>() / image.len() as f64;
+        image
+            .iter()
+            .map(|x| ((x - mean) * factor + mean + offset).clamp(0.0, 1.0))
+            .collect()
+    }
+}
+
+pub struct GaussianNoise {
+    pub stddev: f64,
+}
+
+impl Augmentation for GaussianNoise {
+    fn apply(&self, image: Vec<f64>, _shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        image
+            .iter()
+            .map(|x| (x + standard_normal(rng) * self.stddev).clamp(0.0, 1.0))
+            .collect()
+    }
+}
+
+fn gaussian_blur(field: &[f64], shape: Shape, sigma: f64) -> Vec<f64> {
+    let radius = (3.0 * sigma).ceil() as i64;
+    let kernel: Vec<f64> = (-radius..=radius)
+        .map(|i| (-(i * i) as f64 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f64 = kernel.iter().sum();
+
+    let blur = |field: &[f64], horizontal: bool| -> Vec<f64> {
+        let mut output = vec![0.0; field.len()];
+        for y in 0..shape.height as i64 {
+            for x in 0..shape.width as i64 {
+                let mut value = 0.0;
+                for (k, weight) in (-radius..=radius).zip(kernel.iter()) {
+                    let (sx, sy) = if horizontal { (x + k, y) } else { (x, y + k) };
+                    let sx = sx.clamp(0, shape.width as i64 - 1) as usize;
+                    let sy = sy.clamp(0, shape.height as i64 - 1) as usize;
+                    value += weight * field[sy * shape.width + sx];
+                }
+                output[y as usize * shape.width + x as usize] = value / total;
+            }
+        }
+        output
+    };
+    blur(&blur(field, true), false)
+}
+
+// Elastic distortion (Simard et al.): random per-pixel displacements smoothed
+// with a Gaussian of `sigma` pixels and scaled by `alpha` pixels.
+pub struct ElasticDistortion {
+    pub alpha: f64,
+    pub sigma: f64,
+}
+
+impl Augmentation for ElasticDistortion {
+    fn apply(&self, image: Vec<f64>, shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        let pixels = Shape::new(shape.width, shape.height, 1);
+        let mut field = || {
+            let noise: Vec<f64> = (0..pixels.len())
+                .map(|_| rng.gen_range(-1.0..=1.0))
+                .collect();
+            gaussian_blur(&noise, pixels, self.sigma)
+        };
+        let (dx, dy) = (field(), field());
+
+        warp(&image, shape, |x, y| {
+            let index = y as usize * shape.width + x as usize;
+            (x + self.alpha * dx[index], y + self.alpha * dy[index])
+        })
+    }
+}
+
+// Zeroes `count` squares of `size` pixels at random positions.
+pub struct Cutout {
+    pub size: usize,
+    pub count: usize,
+}
+
+impl Augmentation for Cutout {
+    fn apply(&self, mut image: Vec<f64>, shape: Shape, rng: &mut StdRng) -> Vec<f64> {
+        for _ in 0..self.count {
+            // The square center can be anywhere, the square is clipped to the image.
+            let cx = rng.gen_range(0..shape.width);
+            let cy = rng.gen_range(0..shape.height);
+            let (left, top) = (
+                cx.saturating_sub(self.size / 2),
+                cy.saturating_sub(self.size / 2),
+            );
+            for y in top..(top + self.size).min(shape.height) {
+                for x in left..(left + self.size).min(shape.width) {
+                    for channel in 0..shape.channels {
+                        image[shape.index(x, y, channel)] = 0.0;
+                    }
+                }
+            }
+        }
+        image
+    }
+}
+
+// Augmentations applied one after the other to images of one shape.
+pub struct Compose {
+    pub shape: Shape,
+    steps: Vec<Box<dyn Augmentation>>,
+}
+
+impl Compose {
+    pub fn new(shape: Shape) -> Compose {
+        Compose {
+            shape,
+            steps: vec![],
+        }
+    }
+
+    pub fn then(mut self, augmentation: impl Augmentation + 'static) -> Compose {
+        self.steps.push(Box::new(augmentation));
+        self
+    }
+
+    pub fn augment(&self, image: Vec<f64>, rng: &mut StdRng) -> Vec<f64> {
+        if image.len() != self.shape.len() {
+            panic!(
+                "Image of {} values does not match shape {:?}",
+                image.len(),
+                self.shape
+            );
+        }
+        self.steps
+            .iter()
+            .fold(image, |image, step| step.apply(image, self.shape, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::data::augmentation::{
+        Augmentation, ColorJitter, Compose, Cutout, ElasticDistortion, GaussianNoise, RandomCrop,
+        RandomFlip, RandomRotation, RandomScaling, RandomTranslation, Shape,
+    };
+    use crate::data::dataset::InMemoryDataset;
+    use crate::data::loader::DataLoader;
+
+    // A 4x4 grayscale image with a single bright pixel at (1, 0).
+    fn image() -> Vec<f64> {
+        let mut image = vec![0.0; 16];
+        image[1] = 1.0;
+        image
+    }
+
+    fn shape() -> Shape {
+        Shape::new(4, 4, 1)
+    }
+
+    #[test]
+    fn geometric_augmentations() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let flipped = RandomFlip::horizontal(1.0).apply(image(), shape(), &mut rng);
+        assert_eq!(flipped[2], 1.0);
+        let flipped = RandomFlip::vertical(1.0).apply(image(), shape(), &mut rng);
+        assert_eq!(flipped[13], 1.0);
+        assert_eq!(
+            RandomFlip::horizontal(0.0).apply(image(), shape(), &mut rng),
+            image()
+        );
+
+        let rotated = RandomRotation { max_degrees: 0.0 }.apply(image(), shape(), &mut rng);
+        assert_eq!(rotated, image());
+        let scaled = RandomScaling { min: 1.0, max: 1.0 }.apply(image(), shape(), &mut rng);
+        assert_eq!(scaled, image());
+
+        for augmented in [
+            RandomCrop { padding: 1 }.apply(image(), shape(), &mut rng),
+            RandomTranslation { max_fraction: 0.25 }.apply(image(), shape(), &mut rng),
+            RandomRotation { max_degrees: 30.0 }.apply(image(), shape(), &mut rng),
+            ElasticDistortion {
+                alpha: 1.0,
+                sigma: 1.0,
+            }
+            .apply(image(), shape(), &mut rng),
+        ] {
+            assert_eq!(augmented.len(), 16);
+            assert!(augmented.iter().all(|x| (0.0..=1.0).contains(x)));
+        }
+    }
+
+    #[test]
+    fn photometric_augmentations() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let gray = vec![0.5; 16];
+
+        let jittered = ColorJitter {
+            brightness: 0.2,
+            contrast: 0.5,
+        }
+        .apply(gray.clone(), shape(), &mut rng);
+        assert!(jittered.windows(2).all(|w| w[0] == w[1]));
+        assert!((jittered[0] - 0.5).abs() <= 0.2);
+
+        let noisy = GaussianNoise { stddev: 0.1 }.apply(gray.clone(), shape(), &mut rng);
+        assert_ne!(noisy, gray);
+
+        let cut = Cutout { size: 2, count: 1 }.apply(gray, shape(), &mut rng);
+        let zeros = cut.iter().filter(|&&x| x == 0.0).count();
+        assert!((1..=4).contains(&zeros));
+    }
+
+    #[test]
+    fn loader_augments_reproducibly() {
+        let augmentations = || {
+            Compose::new(shape())
+                .then(RandomFlip::horizontal(0.5))
+                .then(GaussianNoise { stddev: 0.05 })
+        };
+        let dataset = Arc::new(InMemoryDataset::new(vec![image(); 6], vec![vec![1.0]; 6]));
+
+        let mut first = DataLoader::new(dataset.clone(), 3).augment(augmentations(), 9);
+        let mut second = DataLoader::new(dataset.clone(), 3)
+            .augment(augmentations(), 9)
+            .prefetch(2);
+        for _ in 0..2 {
+            let a: Vec<_> = first.batches().collect();
+            let b: Vec<_> = second.batches().collect();
+            assert_eq!(a, b);
+            assert_ne!(a[0].0.data[0], image());
+        }
+
+        let mut plain = DataLoader::new(dataset, 3);
+        assert_eq!(plain.batches().next().unwrap().0.data[0], image());
+    }
+}
@@ -0,0 +1,436 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use super::dataset::InMemoryDataset;
+
+const MISSING_MARKERS: [&str; 6] = ["", "NA", "N/A", "NaN", "null", "?"];
+
+// What to do with rows that have an empty / `NA` / `?` field in a used column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MissingValues {
+    Drop,
+    // Numeric columns get the value, categorical columns their most frequent category.
+    Fill(f64),
+    // Numeric columns get their mean, categorical columns their most frequent category.
+    Mean,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    // Parsed as a number, fails on anything else.
+    Numeric,
+    // One column per category, in sorted order.
+    OneHot,
+    // A single column with the index of the category in sorted order.
+    Label,
+}
+
+// Reads a CSV file into inputs and targets. Columns are referenced by their
+// header name, or by their index ("0", "1", ...) when the file has no header.
+// Columns that are not numeric are one-hot encoded unless configured otherwise.
+pub struct CsvReader {
+    pub delimiter: char,
+    pub has_header: bool,
+    pub missing_values: MissingValues,
+    features: Option<Vec<String>>,
+    targets: Vec<String>,
+    encodings: BTreeMap<String, Encoding>,
+}
+
+// A parsed table with the column names and category lists needed to
+// interpret the encoded values.
+pub struct Table {
+    pub feature_names: Vec<String>,
+    pub target_names: Vec<String>,
+    pub inputs: Vec<Vec<f64>>,
+    pub targets: Vec<Vec<f64>>,
+    // Sorted categories of every categorical column used.
+    pub categories: BTreeMap<String, Vec<String>>,
+}
+
+impl Table {
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    pub fn into_dataset(self) -> InMemoryDataset {
+        InMemoryDataset::new(self.inputs, self.targets)
+    }
+}
+
+struct Column {
+    name: String,
+    values: Vec<Option<String>>,
+}
+
+enum Encoded {
+    Numeric(Vec<Option<f64>>),
+    Categorical {
+        categories: Vec<String>,
+        values: Vec<Option<usize>>,
+        one_hot: bool,
+    },
+}
+
+impl Default for CsvReader {
+    fn default() -> CsvReader {
+        CsvReader::new()
+    }
+}
+
+impl CsvReader {
+    pub fn new() -> CsvReader {
+        CsvReader {
+            delimiter: ',',
+            has_header: true,
+            missing_values: MissingValues::Drop,
+            features: None,
+            targets: vec![],
+            encodings: BTreeMap::new(),
+        }
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> CsvReader {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn no_header(mut self) -> CsvReader {
+        self.has_header = false;
+        self
+    }
+
+    pub fn missing_values(mut self, missing_values: MissingValues) -> CsvReader {
+        self.missing_values = missing_values;
+        self
+    }
+
+    // Feature columns, by default every column that is not a target.
+    pub fn features(mut self, columns: &[&str]) -> CsvReader {
+        self.features = Some(columns.iter().map(|column| column.to_string()).collect());
+        self
+    }
+
+    pub fn target(mut self, column: &str) -> CsvReader {
+        self.targets.push(column.to_string());
+        self
+    }
+
+    pub fn encoding(mut self, column: &str, encoding: Encoding) -> CsvReader {
+        self.encodings.insert(column.to_string(), encoding);
+        self
+    }
+
+    pub fn read(&self, path: &Path) -> Table {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|error| panic!("Unable to read {}: {}", path.display(), error));
+        self.parse(&text)
+    }
+
+    pub fn parse(&self, text: &str) -> Table {
+        let mut records = parse_records(text, self.delimiter);
+        let names = if self.has_header && !records.is_empty() {
+            records.remove(0)
+        } else {
+            let width = records.first().map_or(0, |record| record.len());
+            (0..width).map(|index| index.to_string()).collect()
+        };
+
+        for (line, record) in records.iter().enumerate() {
+            if record.len() != names.len() {
+                panic!(
+                    "Row {} has {} fields, expected {}",
+                    line + 1,
+                    record.len(),
+                    names.len()
+                );
+            }
+        }
+
+        let position = |name: &String| {
+            names
+                .iter()
+                .position(|column| column == name)
+                .unwrap_or_else(|| panic!("Unknown column {:?}, columns are {:?}", name, names))
+        };
+        let feature_names = match &self.features {
+            Some(features) => features.clone(),
+            None => names
+                .iter()
+                .filter(|name| !self.targets.contains(name))
+                .cloned()
+                .collect(),
+        };
+
+        let column = |name: &String| {
+            let index = position(name);
+            Column {
+                name: name.clone(),
+                values: records
+                    .iter()
+                    .map(|record| {
+                        let value = record[index].trim();
+                        if MISSING_MARKERS.contains(&value) {
+                            None
+                        } else {
+                            Some(value.to_string())
+                        }
+                    })
+                    .collect(),
+            }
+        };
+        let features: Vec<Column> = feature_names.iter().map(column).collect();
+        let targets: Vec<Column> = self.targets.iter().map(column).collect();
+
+        // Rows with missing values are dropped before categories and means are computed.
+        let keep: Vec<bool> = (0..records.len())
+            .map(|row| {
+                self.missing_values != MissingValues::Drop
+                    || features
+                        .iter()
+                        .chain(targets.iter())
+                        .all(|column| column.values[row].is_some())
+            })
+            .collect();
+
+        let mut categories = BTreeMap::new();
+        let mut encode = |columns: Vec<Column>| -> Vec<Encoded> {
+            columns
+                .into_iter()
+                .map(|column| {
+                    let values: Vec<Option<String>> = column
+                        .values
+                        .into_iter()
+                        .zip(keep.iter())
+                        .filter(|(_, &keep)| keep)
+                        .map(|(value, _)| value)
+                        .collect();
+                    let encoded = self.encode(&column.name, values);
+                    if let Encoded::Categorical {
+                        categories: names, ..
+                    } = &encoded
+                    {
+                        categories.insert(column.name, names.clone());
+                    }
+                    encoded
+                })
+                .collect()
+        };
+        let features = encode(features);
+        let targets = encode(targets);
+
+        let rows = keep.iter().filter(|&&keep| keep).count();
+        Table {
+            feature_names,
+            target_names: self.targets.clone(),
+            inputs: self.assemble(&features, rows),
+            targets: self.assemble(&targets, rows),
+            categories,
+        }
+    }
+
+    fn encode(&self, name: &str, values: Vec<Option<String>>) -> Encoded {
+        let numeric: Option<Vec<Option<f64>>> = values
+            .iter()
+            .map(|value| match value {
+                Some(value) => value.parse::<f64>().ok().map(Some),
+                None => Some(None),
+            })
+            .collect();
+
+        let encoding = match self.encodings.get(name) {
+            Some(encoding) => *encoding,
+            None if numeric.is_some() => Encoding::Numeric,
+            None => Encoding::OneHot,
+        };
+
+        if encoding == Encoding::Numeric {
+            return match numeric {
+                Some(numeric) => Encoded::Numeric(numeric),
+                None => panic!("Column {:?} contains values that are not numbers", name),
+            };
+        }
+
+        let mut categories: Vec<String> = values.iter().flatten().cloned().collect();
+        categories.sort();
+        categories.dedup();
+        let values = values
+            .iter()
+            .map(|value| {
+                value
+                    .as_ref()
+                    .map(|value| categories.binary_search(value).unwrap())
+            })
+            .collect();
+
+        Encoded::Categorical {
+            categories,
+            values,
+            one_hot: encoding == Encoding::OneHot,
+        }
+    }
+
+    fn assemble(&self, columns: &[Encoded], rows: usize) -> Vec<Vec<f64>> {
+        let mut data = vec![vec![]; rows];
+        for column in columns {
+            match column {
+                Encoded::Numeric(values) => {
+                    let present: Vec<f64> = values.iter().flatten().copied().collect();
+                    let fill = match self.missing_values {
+                        MissingValues::Fill(value) => value,
+                        _ => present.iter().sum::<f64>() / present.len().max(1) as f64,
+                    };
+                    for (row, value) in data.iter_mut().zip(values.iter()) {
+                        row.push(value.unwrap_or(fill));
+                    }
+                }
+                Encoded::Categorical {
+                    categories,
+                    values,
+                    one_hot,
+                } => {
+                    let mut counts = vec![0; categories.len()];
+                    for &value in values.iter().flatten() {
+                        counts[value] += 1;
+                    }
+                    // Ties go to the first category in sorted order.
+                    let most_frequent = (0..counts.len())
+                        .max_by_key(|&index| (counts[index], std::cmp::Reverse(index)))
+                        .unwrap_or(0);
+
+                    for (row, value) in data.iter_mut().zip(values.iter()) {
+                        let value = value.unwrap_or(most_frequent);
+                        if *one_hot {
+                            let mut encoded = vec![0.0; categories.len()];
+                            encoded[value] = 1.0;
+                            row.extend(encoded);
+                        } else {
+                            row.push(value as f64);
+                        }
+                    }
+                }
+            }
+        }
+        data
+    }
+}
+
+// Splits CSV text into records, handling quoted fields with escaped quotes,
+// delimiters and line breaks. Blank lines are skipped.
+fn parse_records(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => quoted = true,
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            _ if c == delimiter => record.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        panic!("Unterminated quoted field");
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::csv::{parse_records, CsvReader, Encoding, MissingValues};
+
+    const IRIS: &str = "sepal_length,sepal_width,species\n\
+                        5.1,3.5,setosa\n\
+                        7.0,3.2,versicolor\n\
+                        6.3,,virginica\n\
+                        4.9,3.0,setosa\n";
+
+    #[test]
+    fn parses_quoted_fields() {
+        let records = parse_records("a,\"b,c\"\r\n\n\"say \"\"hi\"\"\",d", ',');
+        assert_eq!(records, vec![vec!["a", "b,c"], vec!["say \"hi\"", "d"]]);
+    }
+
+    #[test]
+    fn reads_features_and_one_hot_targets() {
+        let table = CsvReader::new().target("species").parse(IRIS);
+
+        assert_eq!(table.feature_names, vec!["sepal_length", "sepal_width"]);
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.inputs[1], vec![7.0, 3.2]);
+        assert_eq!(table.targets[1], vec![0.0, 1.0]);
+        assert_eq!(table.categories["species"], vec!["setosa", "versicolor"]);
+    }
+
+    #[test]
+    fn fills_missing_values() {
+        let table = CsvReader::new()
+            .target("species")
+            .encoding("species", Encoding::Label)
+            .missing_values(MissingValues::Mean)
+            .parse(IRIS);
+
+        assert_eq!(table.len(), 4);
+        assert!((table.inputs[2][1] - 3.233_333_333_333_333).abs() < 1e-12);
+        assert_eq!(
+            table.targets,
+            vec![vec![0.0], vec![1.0], vec![2.0], vec![0.0]]
+        );
+
+        let table = CsvReader::new()
+            .target("species")
+            .missing_values(MissingValues::Fill(-1.0))
+            .parse(IRIS);
+        assert_eq!(table.inputs[2], vec![6.3, -1.0]);
+    }
+
+    #[test]
+    fn selects_columns_without_header() {
+        let table = CsvReader::new()
+            .no_header()
+            .delimiter(';')
+            .features(&["2", "0"])
+            .target("1")
+            .parse("1;red;3\n4;blue;6\n");
+
+        assert_eq!(table.inputs, vec![vec![3.0, 1.0], vec![6.0, 4.0]]);
+        assert_eq!(table.targets, vec![vec![0.0, 1.0], vec![1.0, 0.0]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown column")]
+    fn rejects_unknown_columns() {
+        CsvReader::new().target("class").parse(IRIS);
+    }
+}
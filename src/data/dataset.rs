@@ -0,0 +1,74 @@
+// One training example: (inputs, targets).
+pub type Sample = (Vec<f64>, Vec<f64>);
+
+pub trait Dataset: Send + Sync {
+    fn len(&self) -> usize;
+
+    fn get(&self, index: usize) -> Sample;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub struct InMemoryDataset {
+    pub inputs: Vec<Vec<f64>>,
+    pub targets: Vec<Vec<f64>>,
+}
+
+impl InMemoryDataset {
+    pub fn new(inputs: Vec<Vec<f64>>, targets: Vec<Vec<f64>>) -> InMemoryDataset {
+        if inputs.len() != targets.len() {
+            panic!(
+                "Dataset has {} inputs and {} targets",
+                inputs.len(),
+                targets.len()
+            );
+        }
+        InMemoryDataset { inputs, targets }
+    }
+}
+
+impl Dataset for InMemoryDataset {
+    fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn get(&self, index: usize) -> Sample {
+        (self.inputs[index].clone(), self.targets[index].clone())
+    }
+}
+
+// Produces samples on demand, e.g. decoding files only when a batch needs them.
+pub struct LazyDataset {
+    len: usize,
+    loader: Box<dyn Fn(usize) -> Sample + Send + Sync>,
+}
+
+impl LazyDataset {
+    pub fn new(
+        len: usize,
+        loader: impl Fn(usize) -> Sample + Send + Sync + 'static,
+    ) -> LazyDataset {
+        LazyDataset {
+            len,
+            loader: Box::new(loader),
+        }
+    }
+}
+
+impl Dataset for LazyDataset {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, index: usize) -> Sample {
+        if index >= self.len {
+            panic!(
+                "Sample index {} is out of range for dataset of {} samples",
+                index, self.len
+            );
+        }
+        (self.loader)(index)
+    }
+}
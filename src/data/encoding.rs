@@ -0,0 +1,144 @@
+use std::f64::consts::PI;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::nn::matrix::{standard_normal, Matrix};
+
+// How coordinate inputs (e.g. `(x / width, y / height)`) are presented to a
+// coordinate network. Plain coordinates make networks learn only smooth,
+// blurry functions, the periodic encodings let them fit fine detail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CoordinateEncoding {
+    Raw,
+    // Random Fourier features [sin(2π Bx), cos(2π Bx)] with B sampled from
+    // N(0, scale²), `features` rows of B. Larger scales fit higher frequencies.
+    Fourier {
+        features: usize,
+        scale: f64,
+        seed: u64,
+    },
+    // The coordinates followed by sin(2^k π x) and cos(2^k π x) for
+    // k in 0..frequencies, as in NeRF.
+    Positional {
+        frequencies: usize,
+    },
+}
+
+impl CoordinateEncoding {
+    pub fn output_size(&self, dimensions: usize) -> usize {
+        match self {
+            CoordinateEncoding::Raw => dimensions,
+            CoordinateEncoding::Fourier { features, .. } => 2 * features,
+            CoordinateEncoding::Positional { frequencies } => dimensions * (1 + 2 * frequencies),
+        }
+    }
+
+    // The Fourier projection is sampled again from the seed on every call, so
+    // any grid encoded later matches the training inputs.
+    pub fn encode(&self, coordinates: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        if coordinates.is_empty() {
+            return vec![];
+        }
+
+        match self {
+            CoordinateEncoding::Raw => coordinates.to_vec(),
+            CoordinateEncoding::Fourier {
+                features,
+                scale,
+                seed,
+            } => {
+                let projection = fourier_projection(coordinates[0].len(), *features, *scale, *seed);
+                let projected = Matrix::from(coordinates.to_vec()).dot_product(&projection);
+                projected
+                    .data
+                    .iter()
+                    .map(|row| {
+                        let sin = row.iter().map(|x| (2.0 * PI * x).sin());
+                        let cos = row.iter().map(|x| (2.0 * PI * x).cos());
+                        sin.chain(cos).collect()
+                    })
+                    .collect()
+            }
+            CoordinateEncoding::Positional { frequencies } => coordinates
+                .iter()
+                .map(|row| {
+                    let mut encoded = row.clone();
+                    for k in 0..*frequencies {
+                        let frequency = 2f64.powi(k as i32) * PI;
+                        encoded.extend(row.iter().map(|x| (frequency * x).sin()));
+                        encoded.extend(row.iter().map(|x| (frequency * x).cos()));
+                    }
+                    encoded
+                })
+                .collect(),
+        }
+    }
+}
+
+fn fourier_projection(dimensions: usize, features: usize, scale: f64, seed: u64) -> Matrix {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut projection = Matrix::zeros(dimensions, features);
+    for row in projection.data.iter_mut() {
+        for value in row.iter_mut() {
+            *value = standard_normal(&mut rng) * scale;
+        }
+    }
+    projection
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::data::encoding::CoordinateEncoding;
+
+    #[test]
+    fn positional_encoding() {
+        let encoding = CoordinateEncoding::Positional { frequencies: 2 };
+        let encoded = encoding.encode(&[vec![0.25, 0.5]]);
+
+        assert_eq!(encoded[0].len(), encoding.output_size(2));
+        let expected = [
+            0.25,
+            0.5,
+            FRAC_1_SQRT_2,
+            1.0,
+            FRAC_1_SQRT_2,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+            -1.0,
+        ];
+        for (value, expected) in encoded[0].iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-9, "{:?}", encoded);
+        }
+    }
+
+    #[test]
+    fn fourier_features_are_seeded() {
+        let encoding = CoordinateEncoding::Fourier {
+            features: 8,
+            scale: 10.0,
+            seed: 3,
+        };
+        let coordinates = vec![vec![0.1, 0.2], vec![0.7, 0.4]];
+        let encoded = encoding.encode(&coordinates);
+
+        assert_eq!(encoded[0].len(), 16);
+        assert_eq!(encoded, encoding.encode(&coordinates));
+        // sin² + cos² of every projected frequency is 1.
+        for row in &encoded {
+            for k in 0..8 {
+                assert!((row[k].powi(2) + row[k + 8].powi(2) - 1.0).abs() < 1e-12);
+            }
+        }
+
+        let other = CoordinateEncoding::Fourier {
+            features: 8,
+            scale: 10.0,
+            seed: 4,
+        };
+        assert_ne!(encoded, other.encode(&coordinates));
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+
+use super::{
+    dataset::{Dataset, Sample},
+    mnist::one_hot,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorSpace {
+    Grayscale,
+    Rgb,
+}
+
+impl ColorSpace {
+    pub fn channels(&self) -> usize {
+        match self {
+            ColorSpace::Grayscale => 1,
+            ColorSpace::Rgb => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resize {
+    // Scales to the target size, ignoring the aspect ratio.
+    Stretch,
+    // Scales to cover the target size keeping the aspect ratio, then crops the center.
+    CenterCrop,
+}
+
+// Images in `root/<label>/**/<file>`, one class per subfolder in sorted
+// order. Images are decoded when a sample is requested and flattened row by
+// row with interleaved channels, values in [0, 1]; targets are one-hot.
+pub struct ImageFolder {
+    pub classes: Vec<String>,
+    pub samples: Vec<(PathBuf, usize)>,
+    pub width: u32,
+    pub height: u32,
+    pub color_space: ColorSpace,
+    pub resize: Resize,
+}
+
+fn image_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(directory)
+        .unwrap_or_else(|error| panic!("Unable to read {}: {}", directory.display(), error));
+    for entry in entries {
+        let path = entry.expect("Unable to read directory entry").path();
+        if path.is_dir() {
+            image_files(&path, files);
+        } else if ImageFormat::from_path(&path).is_ok() {
+            files.push(path);
+        }
+    }
+}
+
+impl ImageFolder {
+    pub fn new(root: &Path, width: u32, height: u32) -> ImageFolder {
+        if width == 0 || height == 0 {
+            panic!("Invalid image size {}x{}", width, height);
+        }
+
+        let mut directories: Vec<PathBuf> = fs::read_dir(root)
+            .unwrap_or_else(|error| panic!("Unable to read {}: {}", root.display(), error))
+            .map(|entry| entry.expect("Unable to read directory entry").path())
+            .filter(|path| path.is_dir())
+            .collect();
+        directories.sort();
+        if directories.is_empty() {
+            panic!("{} has no class subfolders", root.display());
+        }
+
+        let mut classes = vec![];
+        let mut samples = vec![];
+        for (label, directory) in directories.iter().enumerate() {
+            let mut files = vec![];
+            image_files(directory, &mut files);
+            files.sort();
+            samples.extend(files.into_iter().map(|file| (file, label)));
+            classes.push(
+                directory
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+
+        ImageFolder {
+            classes,
+            samples,
+            width,
+            height,
+            color_space: ColorSpace::Rgb,
+            resize: Resize::Stretch,
+        }
+    }
+
+    pub fn color_space(mut self, color_space: ColorSpace) -> ImageFolder {
+        self.color_space = color_space;
+        self
+    }
+
+    pub fn resize(mut self, resize: Resize) -> ImageFolder {
+        self.resize = resize;
+        self
+    }
+
+    // Length of a sample input.
+    pub fn input_size(&self) -> usize {
+        (self.width * self.height) as usize * self.color_space.channels()
+    }
+
+    pub fn labels(&self) -> Vec<usize> {
+        self.samples.iter().map(|(_, label)| *label).collect()
+    }
+
+    pub fn load(&self, path: &Path) -> Vec<f64> {
+        let image = image::open(path)
+            .unwrap_or_else(|error| panic!("Unable to decode {}: {}", path.display(), error));
+        self.tensor(&image)
+    }
+
+    fn tensor(&self, image: &DynamicImage) -> Vec<f64> {
+        let image = match self.resize {
+            Resize::Stretch => image.resize_exact(self.width, self.height, FilterType::Triangle),
+            Resize::CenterCrop => {
+                image.resize_to_fill(self.width, self.height, FilterType::Triangle)
+            }
+        };
+        let bytes = match self.color_space {
+            ColorSpace::Grayscale => image.to_luma8().into_raw(),
+            ColorSpace::Rgb => image.to_rgb8().into_raw(),
+        };
+        bytes
+            .into_iter()
+            .map(|value| value as f64 / 255.0)
+            .collect()
+    }
+}
+
+impl Dataset for ImageFolder {
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn get(&self, index: usize) -> Sample {
+        let (path, label) = &self.samples[index];
+        (
+            self.load(path),
+            one_hot(&[*label], self.classes.len()).remove(0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use image::{Rgb, RgbImage};
+
+    use crate::data::dataset::Dataset;
+    use crate::data::image_folder::{ColorSpace, ImageFolder, Resize};
+
+    #[test]
+    fn loads_labelled_images() {
+        let root = std::env::temp_dir().join("rust_nn_image_folder");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("cats/nested")).unwrap();
+        fs::create_dir_all(root.join("dogs")).unwrap();
+
+        RgbImage::from_pixel(4, 2, Rgb([255, 0, 0]))
+            .save(root.join("cats/nested/a.png"))
+            .unwrap();
+        let mut dog = RgbImage::from_pixel(4, 4, Rgb([0, 0, 0]));
+        dog.put_pixel(0, 0, Rgb([255, 255, 255]));
+        dog.save(root.join("dogs/b.png")).unwrap();
+        fs::write(root.join("dogs/notes.txt"), "not an image").unwrap();
+
+        let folder = ImageFolder::new(&root, 2, 2);
+        assert_eq!(folder.classes, vec!["cats", "dogs"]);
+        assert_eq!(folder.len(), 2);
+        assert_eq!(folder.labels(), vec![0, 1]);
+
+        let (cat, target) = folder.get(0);
+        assert_eq!(cat.len(), folder.input_size());
+        assert_eq!(&cat[..3], &[1.0, 0.0, 0.0]);
+        assert_eq!(target, vec![1.0, 0.0]);
+
+        let folder = folder
+            .color_space(ColorSpace::Grayscale)
+            .resize(Resize::CenterCrop);
+        let (dog, target) = folder.get(1);
+        assert_eq!(dog.len(), 4);
+        assert!(dog[0] > dog[3]);
+        assert_eq!(target, vec![0.0, 1.0]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    panic,
+    sync::{
+        mpsc::{sync_channel, Receiver},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::{augmentation::Compose, dataset::Dataset};
+use crate::nn::matrix::Matrix;
+
+// (inputs, targets) of one batch, one sample per row.
+pub type Batch = (Matrix, Matrix);
+
+// Sample indices of a batch, each with the seed of its augmentation.
+type Chunk = Vec<(usize, u64)>;
+
+fn collect_batch(dataset: &dyn Dataset, augmentation: Option<&Compose>, chunk: &Chunk) -> Batch {
+    let (inputs, targets): (Vec<Vec<f64>>, Vec<Vec<f64>>) = chunk
+        .iter()
+        .map(|&(index, seed)| {
+            let (input, target) = dataset.get(index);
+            match augmentation {
+                Some(augmentation) => (
+                    augmentation.augment(input, &mut StdRng::seed_from_u64(seed)),
+                    target,
+                ),
+                None => (input, target),
+            }
+        })
+        .unzip();
+    (Matrix::from(inputs), Matrix::from(targets))
+}
+
+pub struct DataLoader {
+    pub batch_size: usize,
+    pub drop_last: bool,
+    // Number of batches prepared ahead on a background thread, 0 disables it.
+    pub prefetch: usize,
+    dataset: Arc<dyn Dataset>,
+    order: Vec<usize>,
+    rng: Option<StdRng>,
+    augmentation: Option<(Arc<Compose>, StdRng)>,
+}
+
+impl DataLoader {
+    pub fn new(dataset: Arc<dyn Dataset>, batch_size: usize) -> DataLoader {
+        if batch_size == 0 {
+            panic!("Batch size must be greater than zero");
+        }
+
+        DataLoader {
+            batch_size,
+            drop_last: false,
+            prefetch: 0,
+            order: (0..dataset.len()).collect(),
+            dataset,
+            rng: None,
+            augmentation: None,
+        }
+    }
+
+    // Reshuffles the samples at the start of every pass.
+    pub fn shuffle(mut self, seed: u64) -> DataLoader {
+        self.rng = Some(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    pub fn drop_last(mut self) -> DataLoader {
+        self.drop_last = true;
+        self
+    }
+
+    // Augments every input as it is loaded, differently on every pass. The
+    // seeds are drawn before the batches are collected, so prefetching
+    // doesn't change the result.
+    pub fn augment(mut self, augmentation: Compose, seed: u64) -> DataLoader {
+        self.augmentation = Some((Arc::new(augmentation), StdRng::seed_from_u64(seed)));
+        self
+    }
+
+    pub fn prefetch(mut self, batches: usize) -> DataLoader {
+        self.prefetch = batches;
+        self
+    }
+
+    pub fn dataset(&self) -> Arc<dyn Dataset> {
+        self.dataset.clone()
+    }
+
+    pub fn samples(&self) -> usize {
+        if self.drop_last {
+            self.len() * self.batch_size
+        } else {
+            self.dataset.len()
+        }
+    }
+
+    // Number of batches in one pass.
+    pub fn len(&self) -> usize {
+        if self.drop_last {
+            self.dataset.len() / self.batch_size
+        } else {
+            self.dataset.len().div_ceil(self.batch_size)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // One pass over the dataset.
+    pub fn batches(&mut self) -> Batches {
+        if self.order.len() != self.dataset.len() {
+            self.order = (0..self.dataset.len()).collect();
+        }
+        if let Some(rng) = self.rng.as_mut() {
+            self.order.shuffle(rng);
+        }
+
+        let seeds: Vec<u64> = match self.augmentation.as_mut() {
+            Some((_, rng)) => (0..self.order.len()).map(|_| rng.gen()).collect(),
+            None => vec![0; self.order.len()],
+        };
+        let pairs: Chunk = self.order.iter().copied().zip(seeds).collect();
+        let mut chunks: Vec<Chunk> = pairs
+            .chunks(self.batch_size)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        if self.drop_last && chunks.last().map(|chunk| chunk.len()) != Some(self.batch_size) {
+            chunks.pop();
+        }
+
+        let augmentation = self
+            .augmentation
+            .as_ref()
+            .map(|(augmentation, _)| augmentation.clone());
+        if self.prefetch == 0 {
+            return Batches::Direct {
+                dataset: self.dataset.clone(),
+                augmentation,
+                chunks: chunks.into_iter(),
+            };
+        }
+
+        let (sender, receiver) = sync_channel(self.prefetch);
+        let dataset = self.dataset.clone();
+        let worker = thread::spawn(move || {
+            for chunk in chunks {
+                // The receiver is gone when the pass was abandoned.
+                if sender
+                    .send(collect_batch(
+                        dataset.as_ref(),
+                        augmentation.as_deref(),
+                        &chunk,
+                    ))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        Batches::Prefetched {
+            receiver,
+            worker: Some(worker),
+        }
+    }
+}
+
+pub enum Batches {
+    Direct {
+        dataset: Arc<dyn Dataset>,
+        augmentation: Option<Arc<Compose>>,
+        chunks: std::vec::IntoIter<Chunk>,
+    },
+    Prefetched {
+        receiver: Receiver<Batch>,
+        worker: Option<JoinHandle<()>>,
+    },
+}
+
+impl Iterator for Batches {
+    type Item = Batch;
+
+    fn next(&mut self) -> Option<Batch> {
+        match self {
+            Batches::Direct {
+                dataset,
+                augmentation,
+                chunks,
+            } => chunks
+                .next()
+                .map(|chunk| collect_batch(dataset.as_ref(), augmentation.as_deref(), &chunk)),
+            Batches::Prefetched { receiver, worker } => match receiver.recv() {
+                Ok(batch) => Some(batch),
+                // The worker hung up, either done or panicking on a sample,
+                // which is raised here rather than ending the pass early.
+                Err(_) => {
+                    if let Some(Err(payload)) = worker.take().map(JoinHandle::join) {
+                        panic::resume_unwind(payload);
+                    }
+                    None
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::data::dataset::{InMemoryDataset, LazyDataset};
+    use crate::data::loader::DataLoader;
+
+    fn dataset() -> Arc<InMemoryDataset> {
+        let inputs = (0..10).map(|i| vec![i as f64]).collect();
+        let targets = (0..10).map(|i| vec![2.0 * i as f64]).collect();
+        Arc::new(InMemoryDataset::new(inputs, targets))
+    }
+
+    #[test]
+    fn batches_keep_order_without_shuffle() {
+        let mut loader = DataLoader::new(dataset(), 4);
+        let batches: Vec<_> = loader.batches().collect();
+
+        assert_eq!(loader.len(), 3);
+        assert_eq!(batches.len(), 3);
+        assert_eq!(
+            batches[0].0.data,
+            vec![vec![0.0], vec![1.0], vec![2.0], vec![3.0]]
+        );
+        assert_eq!(batches[2].1.data, vec![vec![16.0], vec![18.0]]);
+    }
+
+    #[test]
+    fn drop_last_skips_partial_batch() {
+        let mut loader = DataLoader::new(dataset(), 4).drop_last();
+        assert_eq!(loader.len(), 2);
+        assert_eq!(loader.samples(), 8);
+        assert!(loader.batches().all(|(inputs, _)| inputs.rows == 4));
+    }
+
+    #[test]
+    fn shuffled_prefetch_matches_direct() {
+        let mut direct = DataLoader::new(dataset(), 3).shuffle(5);
+        let mut prefetched = DataLoader::new(dataset(), 3).shuffle(5).prefetch(2);
+
+        for _ in 0..3 {
+            let first: Vec<_> = direct.batches().collect();
+            let second: Vec<_> = prefetched.batches().collect();
+            assert_eq!(first, second);
+        }
+
+        let mut seen: Vec<f64> = direct
+            .batches()
+            .flat_map(|(inputs, _)| inputs.data.into_iter().map(|row| row[0]))
+            .collect();
+        seen.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(seen, (0..10).map(|i| i as f64).collect::<Vec<f64>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "Sample 7 is broken")]
+    fn prefetch_raises_dataset_panics() {
+        let dataset = LazyDataset::new(10, |index| {
+            if index == 7 {
+                panic!("Sample {} is broken", index);
+            }
+            (vec![index as f64], vec![1.0])
+        });
+        let mut loader = DataLoader::new(Arc::new(dataset), 3).prefetch(2);
+        loader.batches().for_each(drop);
+    }
+
+    #[test]
+    fn lazy_dataset_loads_on_demand() {
+        let dataset = LazyDataset::new(5, |index| (vec![index as f64], vec![1.0]));
+        let mut loader = DataLoader::new(Arc::new(dataset), 5);
+        let (inputs, targets) = loader.batches().next().unwrap();
+        assert_eq!(inputs.data[4], vec![4.0]);
+        assert_eq!(targets.data[0], vec![1.0]);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fs::File, io::Read, path::Path};
+
+use flate2::read::GzDecoder;
+
+use super::dataset::InMemoryDataset;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const UNSIGNED_BYTE: u8 = 0x08;
+
+// Contents of an IDX file of unsigned bytes, e.g. `train-images-idx3-ubyte`.
+pub struct Idx {
+    pub dimensions: Vec<usize>,
+    pub data: Vec<u8>,
+}
+
+// Reads an IDX file, transparently inflating it when it is gzipped.
+pub fn read_idx(path: &Path) -> Idx {
+    let mut bytes = vec![];
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .unwrap_or_else(|error| panic!("Unable to read {}: {}", path.display(), error));
+
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut inflated = vec![];
+        GzDecoder::new(&bytes[..])
+            .read_to_end(&mut inflated)
+            .unwrap_or_else(|error| panic!("Unable to inflate {}: {}", path.display(), error));
+        bytes = inflated;
+    }
+
+    parse_idx(&bytes)
+}
+
+pub fn parse_idx(bytes: &[u8]) -> Idx {
+    if bytes.len() < 4 || bytes[0] != 0 || bytes[1] != 0 {
+        panic!("Not an IDX file");
+    }
+    if bytes[2] != UNSIGNED_BYTE {
+        panic!(
+            "Unsupported IDX data type 0x{:02x}, only unsigned bytes are supported",
+            bytes[2]
+        );
+    }
+
+    let header = 4 + 4 * bytes[3] as usize;
+    if bytes.len() < header {
+        panic!("IDX header is truncated");
+    }
+    let dimensions: Vec<usize> = bytes[4..header]
+        .chunks(4)
+        .map(|size| u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize)
+        .collect();
+
+    let len: usize = dimensions.iter().product();
+    if bytes.len() - header != len {
+        panic!(
+            "IDX file with dimensions {:?} should hold {} values, found {}",
+            dimensions,
+            len,
+            bytes.len() - header
+        );
+    }
+
+    Idx {
+        dimensions,
+        data: bytes[header..].to_vec(),
+    }
+}
+
+pub fn one_hot(labels: &[usize], classes: usize) -> Vec<Vec<f64>> {
+    labels
+        .iter()
+        .map(|&label| {
+            if label >= classes {
+                panic!("Label {} is out of range for {} classes", label, classes);
+            }
+            let mut row = vec![0.0; classes];
+            row[label] = 1.0;
+            row
+        })
+        .collect()
+}
+
+pub struct Mnist {
+    // One flattened image per row, row-major, pixels scaled to [0, 1].
+    pub images: Vec<Vec<f64>>,
+    pub labels: Vec<usize>,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+impl Mnist {
+    pub const CLASSES: usize = 10;
+
+    pub fn new(images: Idx, labels: Idx) -> Mnist {
+        if images.dimensions.len() != 3 {
+            panic!(
+                "Expected images with 3 dimensions, found {:?}",
+                images.dimensions
+            );
+        }
+        if labels.dimensions.len() != 1 {
+            panic!(
+                "Expected labels with 1 dimension, found {:?}",
+                labels.dimensions
+            );
+        }
+        if images.dimensions[0] != labels.dimensions[0] {
+            panic!(
+                "Found {} images and {} labels",
+                images.dimensions[0], labels.dimensions[0]
+            );
+        }
+
+        let (rows, columns) = (images.dimensions[1], images.dimensions[2]);
+        let images = images
+            .data
+            .chunks(rows * columns)
+            .map(|image| image.iter().map(|&pixel| pixel as f64 / 255.0).collect())
+            .collect();
+        let labels = labels.data.iter().map(|&label| label as usize).collect();
+
+        Mnist {
+            images,
+            labels,
+            rows,
+            columns,
+        }
+    }
+
+    pub fn load(images: &Path, labels: &Path) -> Mnist {
+        Mnist::new(read_idx(images), read_idx(labels))
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    // Keeps the first `len` samples.
+    pub fn truncate(&mut self, len: usize) {
+        self.images.truncate(len);
+        self.labels.truncate(len);
+    }
+
+    pub fn targets(&self) -> Vec<Vec<f64>> {
+        one_hot(&self.labels, Mnist::CLASSES)
+    }
+
+    pub fn into_dataset(self) -> InMemoryDataset {
+        let targets = self.targets();
+        InMemoryDataset::new(self.images, targets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use crate::data::dataset::Dataset;
+    use crate::data::mnist::{one_hot, parse_idx, read_idx, Mnist};
+
+    fn idx(dimensions: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 0x08, dimensions.len() as u8];
+        for dimension in dimensions {
+            bytes.extend(dimension.to_be_bytes());
+        }
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn parses_images_and_labels() {
+        let images = parse_idx(&idx(&[2, 2, 2], &[0, 255, 51, 0, 255, 255, 0, 0]));
+        let labels = parse_idx(&idx(&[2], &[3, 9]));
+        assert_eq!(images.dimensions, vec![2, 2, 2]);
+
+        let mnist = Mnist::new(images, labels);
+        assert_eq!((mnist.rows, mnist.columns), (2, 2));
+        assert_eq!(mnist.images[0], vec![0.0, 1.0, 0.2, 0.0]);
+        assert_eq!(mnist.labels, vec![3, 9]);
+
+        let dataset = mnist.into_dataset();
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.get(1).1[9], 1.0);
+    }
+
+    #[test]
+    fn reads_gzipped_files() {
+        let path = std::env::temp_dir().join("rust_nn_mnist_labels.gz");
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&idx(&[3], &[1, 2, 3])).unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let labels = read_idx(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(labels.dimensions, vec![3]);
+        assert_eq!(labels.data, vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "should hold 4 values")]
+    fn rejects_truncated_data() {
+        parse_idx(&idx(&[2, 2], &[1, 2, 3]));
+    }
+
+    #[test]
+    fn encodes_one_hot() {
+        assert_eq!(
+            one_hot(&[0, 2], 3),
+            vec![vec![1.0, 0.0, 0.0], vec![0.0, 0.0, 1.0]]
+        );
+    }
+}
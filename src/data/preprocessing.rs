@@ -0,0 +1,727 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string};
+
+// A preprocessing step learned from training data, applied identically to
+// every later input. Rows are samples, columns are features.
+pub trait Transformer {
+    fn fit(&mut self, data: &[Vec<f64>]);
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>>;
+
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>>;
+
+    fn fit_transform(&mut self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.fit(data);
+        self.transform(data)
+    }
+}
+
+fn check_columns(name: &str, expected: usize, data: &[Vec<f64>]) {
+    if expected == 0 {
+        panic!("{} is not fitted", name);
+    }
+    if let Some(row) = data.iter().find(|row| row.len() != expected) {
+        panic!(
+            "{} was fitted on {} columns, got a row of {}",
+            name,
+            expected,
+            row.len()
+        );
+    }
+}
+
+fn column(data: &[Vec<f64>], index: usize) -> Vec<f64> {
+    data.iter().map(|row| row[index]).collect()
+}
+
+fn columns(data: &[Vec<f64>]) -> usize {
+    match data.first() {
+        Some(row) => row.len(),
+        None => panic!("Cannot fit on an empty dataset"),
+    }
+}
+
+// Linear interpolation between the closest ranks, as numpy's default.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+// Applies `(x - offset) / scale` per column.
+fn scale(data: &[Vec<f64>], offset: &[f64], scale: &[f64]) -> Vec<Vec<f64>> {
+    data.iter()
+        .map(|row| {
+            row.iter()
+                .zip(offset.iter().zip(scale.iter()))
+                .map(|(x, (offset, scale))| (x - offset) / scale)
+                .collect()
+        })
+        .collect()
+}
+
+fn unscale(data: &[Vec<f64>], offset: &[f64], scale: &[f64]) -> Vec<Vec<f64>> {
+    data.iter()
+        .map(|row| {
+            row.iter()
+                .zip(offset.iter().zip(scale.iter()))
+                .map(|(x, (offset, scale))| x * scale + offset)
+                .collect()
+        })
+        .collect()
+}
+
+// Constant columns keep a scale of 1 instead of dividing by zero.
+fn non_zero(scale: f64) -> f64 {
+    if scale == 0.0 {
+        1.0
+    } else {
+        scale
+    }
+}
+
+// Zero mean and unit variance per column.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StandardScaler {
+    pub mean: Vec<f64>,
+    pub std: Vec<f64>,
+}
+
+impl StandardScaler {
+    pub fn new() -> StandardScaler {
+        StandardScaler::default()
+    }
+}
+
+impl Transformer for StandardScaler {
+    fn fit(&mut self, data: &[Vec<f64>]) {
+        let n = data.len() as f64;
+        self.mean = (0..columns(data))
+            .map(|i| column(data, i).iter().sum::<f64>() / n)
+            .collect();
+        self.std = (0..columns(data))
+            .map(|i| {
+                let variance = column(data, i)
+                    .iter()
+                    .map(|x| (x - self.mean[i]).powi(2))
+                    .sum::<f64>()
+                    / n;
+                non_zero(variance.sqrt())
+            })
+            .collect();
+    }
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("StandardScaler", self.mean.len(), data);
+        scale(data, &self.mean, &self.std)
+    }
+
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("StandardScaler", self.mean.len(), data);
+        unscale(data, &self.mean, &self.std)
+    }
+}
+
+// Maps the fitted minimum and maximum of every column onto `range`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MinMaxScaler {
+    pub range: (f64, f64),
+    pub min: Vec<f64>,
+    pub max: Vec<f64>,
+}
+
+impl Default for MinMaxScaler {
+    fn default() -> MinMaxScaler {
+        MinMaxScaler::new(0.0, 1.0)
+    }
+}
+
+impl MinMaxScaler {
+    pub fn new(from: f64, to: f64) -> MinMaxScaler {
+        if from >= to {
+            panic!("Invalid range [{}, {}]", from, to);
+        }
+        MinMaxScaler {
+            range: (from, to),
+            min: vec![],
+            max: vec![],
+        }
+    }
+
+    // Offsets and scales so that `x' = (x - offset) / scale`.
+    fn parameters(&self) -> (Vec<f64>, Vec<f64>) {
+        let (from, to) = self.range;
+        let scales: Vec<f64> = self
+            .min
+            .iter()
+            .zip(self.max.iter())
+            .map(|(min, max)| non_zero(max - min) / (to - from))
+            .collect();
+        let offsets = self
+            .min
+            .iter()
+            .zip(scales.iter())
+            .map(|(min, scale)| min - from * scale)
+            .collect();
+        (offsets, scales)
+    }
+}
+
+impl Transformer for MinMaxScaler {
+    fn fit(&mut self, data: &[Vec<f64>]) {
+        self.min = (0..columns(data))
+            .map(|i| column(data, i).into_iter().fold(f64::INFINITY, f64::min))
+            .collect();
+        self.max = (0..columns(data))
+            .map(|i| {
+                column(data, i)
+                    .into_iter()
+                    .fold(f64::NEG_INFINITY, f64::max)
+            })
+            .collect();
+    }
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("MinMaxScaler", self.min.len(), data);
+        let (offsets, scales) = self.parameters();
+        scale(data, &offsets, &scales)
+    }
+
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("MinMaxScaler", self.min.len(), data);
+        let (offsets, scales) = self.parameters();
+        unscale(data, &offsets, &scales)
+    }
+}
+
+// Removes the median and scales by the interquartile range, so outliers do
+// not dominate the scaling.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RobustScaler {
+    pub median: Vec<f64>,
+    pub iqr: Vec<f64>,
+}
+
+impl RobustScaler {
+    pub fn new() -> RobustScaler {
+        RobustScaler::default()
+    }
+}
+
+impl Transformer for RobustScaler {
+    fn fit(&mut self, data: &[Vec<f64>]) {
+        let sorted: Vec<Vec<f64>> = (0..columns(data))
+            .map(|i| {
+                let mut values = column(data, i);
+                values.sort_by(|a, b| a.total_cmp(b));
+                values
+            })
+            .collect();
+        self.median = sorted.iter().map(|values| quantile(values, 0.5)).collect();
+        self.iqr = sorted
+            .iter()
+            .map(|values| non_zero(quantile(values, 0.75) - quantile(values, 0.25)))
+            .collect();
+    }
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("RobustScaler", self.median.len(), data);
+        scale(data, &self.median, &self.iqr)
+    }
+
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("RobustScaler", self.median.len(), data);
+        unscale(data, &self.median, &self.iqr)
+    }
+}
+
+// Replaces the listed columns of category codes with one column per category
+// seen while fitting (sorted), other columns pass through in place. Unknown
+// categories are encoded as all zeros.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OneHotEncoder {
+    pub columns: Vec<usize>,
+    pub categories: Vec<Vec<f64>>,
+    width: usize,
+}
+
+impl OneHotEncoder {
+    pub fn new(columns: Vec<usize>) -> OneHotEncoder {
+        OneHotEncoder {
+            columns,
+            categories: vec![],
+            width: 0,
+        }
+    }
+
+    fn categories_of(&self, index: usize) -> Option<&Vec<f64>> {
+        self.columns
+            .iter()
+            .position(|&column| column == index)
+            .map(|position| &self.categories[position])
+    }
+}
+
+impl Transformer for OneHotEncoder {
+    fn fit(&mut self, data: &[Vec<f64>]) {
+        self.width = columns(data);
+        if let Some(column) = self.columns.iter().find(|&&column| column >= self.width) {
+            panic!(
+                "Column {} is out of range for {} columns",
+                column, self.width
+            );
+        }
+
+        self.categories = self
+            .columns
+            .iter()
+            .map(|&index| {
+                let mut values = column(data, index);
+                values.sort_by(|a, b| a.total_cmp(b));
+                values.dedup();
+                values
+            })
+            .collect();
+    }
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("OneHotEncoder", self.width, data);
+        data.iter()
+            .map(|row| {
+                let mut encoded = vec![];
+                for (index, &value) in row.iter().enumerate() {
+                    match self.categories_of(index) {
+                        Some(categories) => {
+                            encoded.extend(categories.iter().map(|&category| {
+                                if category == value {
+                                    1.0
+                                } else {
+                                    0.0
+                                }
+                            }));
+                        }
+                        None => encoded.push(value),
+                    }
+                }
+                encoded
+            })
+            .collect()
+    }
+
+    // Decodes every one-hot block with argmax.
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let encoded_width = self.width - self.columns.len()
+            + self.categories.iter().map(|c| c.len()).sum::<usize>();
+        check_columns("OneHotEncoder", encoded_width, data);
+
+        data.iter()
+            .map(|row| {
+                let mut position = 0;
+                (0..self.width)
+                    .map(|index| match self.categories_of(index) {
+                        Some(categories) => {
+                            let block = &row[position..position + categories.len()];
+                            position += categories.len();
+                            let best = (0..block.len())
+                                .max_by(|&a, &b| block[a].total_cmp(&block[b]))
+                                .unwrap();
+                            categories[best]
+                        }
+                        None => {
+                            position += 1;
+                            row[position - 1]
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// Maps the distinct values of every column to 0..n in sorted order, e.g.
+// class ids {3, 7, 9} to {0, 1, 2}.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LabelEncoder {
+    pub classes: Vec<Vec<f64>>,
+}
+
+impl LabelEncoder {
+    pub fn new() -> LabelEncoder {
+        LabelEncoder::default()
+    }
+}
+
+impl Transformer for LabelEncoder {
+    fn fit(&mut self, data: &[Vec<f64>]) {
+        self.classes = (0..columns(data))
+            .map(|index| {
+                let mut values = column(data, index);
+                values.sort_by(|a, b| a.total_cmp(b));
+                values.dedup();
+                values
+            })
+            .collect();
+    }
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("LabelEncoder", self.classes.len(), data);
+        data.iter()
+            .map(|row| {
+                row.iter()
+                    .zip(self.classes.iter())
+                    .map(|(value, classes)| {
+                        classes
+                            .iter()
+                            .position(|class| class == value)
+                            .unwrap_or_else(|| panic!("Unknown label {}", value))
+                            as f64
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("LabelEncoder", self.classes.len(), data);
+        data.iter()
+            .map(|row| {
+                row.iter()
+                    .zip(self.classes.iter())
+                    .map(|(&index, classes)| {
+                        classes[(index.round().max(0.0) as usize).min(classes.len() - 1)]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// All products of the features up to `degree`, ordered by degree:
+// [1, a, b, a², ab, b², ...]. The inverse keeps the degree one terms.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PolynomialFeatures {
+    pub degree: usize,
+    pub include_bias: bool,
+    // Feature indices multiplied together for every output column.
+    pub terms: Vec<Vec<usize>>,
+    width: usize,
+}
+
+impl PolynomialFeatures {
+    pub fn new(degree: usize, include_bias: bool) -> PolynomialFeatures {
+        if degree == 0 {
+            panic!("Degree must be greater than zero");
+        }
+        PolynomialFeatures {
+            degree,
+            include_bias,
+            terms: vec![],
+            width: 0,
+        }
+    }
+}
+
+impl Transformer for PolynomialFeatures {
+    fn fit(&mut self, data: &[Vec<f64>]) {
+        self.width = columns(data);
+
+        let mut terms: Vec<Vec<usize>> = vec![vec![]];
+        let mut previous: Vec<Vec<usize>> = vec![vec![]];
+        for _ in 0..self.degree {
+            // Combinations with replacement, kept in non-decreasing index order.
+            previous = previous
+                .iter()
+                .flat_map(|term| {
+                    let start = term.last().copied().unwrap_or(0);
+                    (start..self.width).map(move |index| {
+                        let mut term = term.clone();
+                        term.push(index);
+                        term
+                    })
+                })
+                .collect();
+            terms.extend(previous.iter().cloned());
+        }
+        if !self.include_bias {
+            terms.remove(0);
+        }
+        self.terms = terms;
+    }
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("PolynomialFeatures", self.width, data);
+        data.iter()
+            .map(|row| {
+                self.terms
+                    .iter()
+                    .map(|term| term.iter().map(|&index| row[index]).product())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        check_columns("PolynomialFeatures", self.terms.len(), data);
+        let linear: Vec<usize> = (0..self.width)
+            .map(|index| {
+                self.terms
+                    .iter()
+                    .position(|term| term == &vec![index])
+                    .unwrap()
+            })
+            .collect();
+        data.iter()
+            .map(|row| linear.iter().map(|&position| row[position]).collect())
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Preprocessor {
+    StandardScaler(StandardScaler),
+    MinMaxScaler(MinMaxScaler),
+    RobustScaler(RobustScaler),
+    OneHotEncoder(OneHotEncoder),
+    LabelEncoder(LabelEncoder),
+    PolynomialFeatures(PolynomialFeatures),
+}
+
+impl Preprocessor {
+    fn transformer(&self) -> &dyn Transformer {
+        match self {
+            Preprocessor::StandardScaler(t) => t,
+            Preprocessor::MinMaxScaler(t) => t,
+            Preprocessor::RobustScaler(t) => t,
+            Preprocessor::OneHotEncoder(t) => t,
+            Preprocessor::LabelEncoder(t) => t,
+            Preprocessor::PolynomialFeatures(t) => t,
+        }
+    }
+
+    fn transformer_mut(&mut self) -> &mut dyn Transformer {
+        match self {
+            Preprocessor::StandardScaler(t) => t,
+            Preprocessor::MinMaxScaler(t) => t,
+            Preprocessor::RobustScaler(t) => t,
+            Preprocessor::OneHotEncoder(t) => t,
+            Preprocessor::LabelEncoder(t) => t,
+            Preprocessor::PolynomialFeatures(t) => t,
+        }
+    }
+}
+
+impl Transformer for Preprocessor {
+    fn fit(&mut self, data: &[Vec<f64>]) {
+        self.transformer_mut().fit(data)
+    }
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.transformer().transform(data)
+    }
+
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.transformer().inverse_transform(data)
+    }
+}
+
+// Steps applied in order, each fitted on the output of the previous one.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pipeline {
+    pub steps: Vec<Preprocessor>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    pub fn then(mut self, step: Preprocessor) -> Pipeline {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn save(&self, file: String) {
+        let mut file = File::create(file).expect("Unable to touch preprocessing file");
+        file.write_all(
+            to_string(self)
+                .expect("Unable to serialize preprocessing")
+                .as_bytes(),
+        )
+        .expect("Unable to write to preprocessing file");
+    }
+
+    pub fn load(file: String) -> Pipeline {
+        let mut file = File::open(file).expect("Unable to open preprocessing file");
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .expect("Unable to read preprocessing file");
+        from_str(&buffer).expect("Unable to deserialize preprocessing")
+    }
+}
+
+impl Transformer for Pipeline {
+    fn fit(&mut self, data: &[Vec<f64>]) {
+        self.fit_transform(data);
+    }
+
+    fn transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.steps
+            .iter()
+            .fold(data.to_vec(), |data, step| step.transform(&data))
+    }
+
+    fn inverse_transform(&self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.steps
+            .iter()
+            .rev()
+            .fold(data.to_vec(), |data, step| step.inverse_transform(&data))
+    }
+
+    fn fit_transform(&mut self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.steps
+            .iter_mut()
+            .fold(data.to_vec(), |data, step| step.fit_transform(&data))
+    }
+}
+
+// Where the preprocessing of a saved model lives: `model.json` keeps its
+// pipeline in `model.preprocessing.json`.
+pub fn path_next_to(model_file: &str) -> String {
+    Path::new(model_file)
+        .with_extension("preprocessing.json")
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::preprocessing::{
+        path_next_to, LabelEncoder, MinMaxScaler, OneHotEncoder, Pipeline, PolynomialFeatures,
+        Preprocessor, RobustScaler, StandardScaler, Transformer,
+    };
+
+    fn data() -> Vec<Vec<f64>> {
+        vec![
+            vec![1.0, 10.0],
+            vec![2.0, 10.0],
+            vec![3.0, 40.0],
+            vec![4.0, 100.0],
+        ]
+    }
+
+    fn assert_close(left: &[Vec<f64>], right: &[Vec<f64>]) {
+        for (l, r) in left.iter().flatten().zip(right.iter().flatten()) {
+            assert!((l - r).abs() < 1e-9, "{:?} != {:?}", left, right);
+        }
+    }
+
+    #[test]
+    fn scalers_round_trip() {
+        let transformers: Vec<Box<dyn Transformer>> = vec![
+            Box::new(StandardScaler::new()),
+            Box::new(MinMaxScaler::new(-1.0, 1.0)),
+            Box::new(RobustScaler::new()),
+        ];
+        for mut transformer in transformers {
+            let scaled = transformer.fit_transform(&data());
+            assert_close(&transformer.inverse_transform(&scaled), &data());
+        }
+    }
+
+    #[test]
+    fn scalers_statistics() {
+        let scaled = StandardScaler::new().fit_transform(&data());
+        let mean: f64 = scaled.iter().map(|row| row[0]).sum::<f64>() / 4.0;
+        let variance: f64 = scaled.iter().map(|row| row[0] * row[0]).sum::<f64>() / 4.0;
+        assert!(mean.abs() < 1e-12 && (variance - 1.0).abs() < 1e-12);
+
+        let scaled = MinMaxScaler::default().fit_transform(&data());
+        assert_close(
+            &scaled,
+            &[
+                vec![0.0, 0.0],
+                vec![1.0 / 3.0, 0.0],
+                vec![2.0 / 3.0, 1.0 / 3.0],
+                vec![1.0, 1.0],
+            ],
+        );
+
+        let mut robust = RobustScaler::new();
+        robust.fit(&data());
+        assert_eq!(robust.median, vec![2.5, 25.0]);
+        assert_eq!(robust.iqr, vec![1.5, 45.0]);
+    }
+
+    #[test]
+    fn encoders() {
+        let data = vec![vec![0.5, 7.0], vec![1.5, 3.0], vec![2.5, 9.0]];
+
+        let mut one_hot = OneHotEncoder::new(vec![1]);
+        let encoded = one_hot.fit_transform(&data);
+        assert_eq!(encoded[0], vec![0.5, 0.0, 1.0, 0.0]);
+        assert_eq!(
+            one_hot.transform(&[vec![1.0, 4.0]]),
+            vec![vec![1.0, 0.0, 0.0, 0.0]]
+        );
+        assert_eq!(one_hot.inverse_transform(&encoded), data);
+
+        let mut labels = LabelEncoder::new();
+        let encoded = labels.fit_transform(&data);
+        assert_eq!(
+            encoded,
+            vec![vec![0.0, 1.0], vec![1.0, 0.0], vec![2.0, 2.0]]
+        );
+        assert_eq!(labels.inverse_transform(&encoded), data);
+    }
+
+    #[test]
+    fn polynomial_features() {
+        let mut polynomial = PolynomialFeatures::new(2, true);
+        let expanded = polynomial.fit_transform(&[vec![2.0, 3.0]]);
+        assert_eq!(expanded, vec![vec![1.0, 2.0, 3.0, 4.0, 6.0, 9.0]]);
+        assert_eq!(
+            polynomial.inverse_transform(&expanded),
+            vec![vec![2.0, 3.0]]
+        );
+
+        let mut polynomial = PolynomialFeatures::new(3, false);
+        assert_eq!(
+            polynomial.fit_transform(&[vec![2.0]]),
+            vec![vec![2.0, 4.0, 8.0]]
+        );
+    }
+
+    #[test]
+    fn pipeline_saves_next_to_model() {
+        let mut pipeline = Pipeline::new()
+            .then(Preprocessor::PolynomialFeatures(PolynomialFeatures::new(
+                2, false,
+            )))
+            .then(Preprocessor::StandardScaler(StandardScaler::new()));
+        let transformed = pipeline.fit_transform(&data());
+        assert_eq!(transformed[0].len(), 5);
+
+        let file = std::env::temp_dir().join("rust_nn_model.json");
+        let file = path_next_to(file.to_str().unwrap());
+        assert!(file.ends_with("rust_nn_model.preprocessing.json"));
+        pipeline.save(file.clone());
+        let loaded = Pipeline::load(file.clone());
+        std::fs::remove_file(file).unwrap();
+
+        assert_close(&loaded.transform(&data()), &transformed);
+        assert_close(&loaded.inverse_transform(&transformed), &data());
+    }
+
+    #[test]
+    #[should_panic(expected = "StandardScaler is not fitted")]
+    fn transform_requires_fit() {
+        StandardScaler::new().transform(&data());
+    }
+}
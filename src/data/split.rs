@@ -0,0 +1,350 @@
+use std::collections::BTreeMap;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::dataset::InMemoryDataset;
+use crate::nn::{
+    metrics::class_index,
+    model::Model,
+    trainer::{History, Trainer},
+};
+
+pub struct Split {
+    pub train: InMemoryDataset,
+    pub validation: InMemoryDataset,
+    pub test: InMemoryDataset,
+}
+
+// Training and held-out sample indices of one fold.
+pub type Fold = (Vec<usize>, Vec<usize>);
+
+fn check_fractions(validation: f64, test: f64) {
+    if validation < 0.0 || test < 0.0 || validation + test >= 1.0 {
+        panic!(
+            "Invalid split fractions: validation {} and test {} must be positive and leave training samples",
+            validation, test
+        );
+    }
+}
+
+fn check_lengths(inputs: &[Vec<f64>], targets: &[Vec<f64>]) {
+    if inputs.len() != targets.len() {
+        panic!(
+            "Dataset has {} inputs and {} targets",
+            inputs.len(),
+            targets.len()
+        );
+    }
+}
+
+fn select(inputs: &[Vec<f64>], targets: &[Vec<f64>], indices: &[usize]) -> InMemoryDataset {
+    InMemoryDataset::new(
+        indices.iter().map(|&i| inputs[i].clone()).collect(),
+        indices.iter().map(|&i| targets[i].clone()).collect(),
+    )
+}
+
+// Sample indices grouped by the class of their target, in class order.
+fn classes(targets: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    let mut classes: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (index, target) in targets.iter().enumerate() {
+        classes.entry(class_index(target)).or_default().push(index);
+    }
+    classes.into_values().collect()
+}
+
+// Shuffles every group and cuts it into (train, validation, test) indices.
+fn partition(groups: Vec<Vec<usize>>, validation: f64, test: f64, seed: u64) -> [Vec<usize>; 3] {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut parts = [vec![], vec![], vec![]];
+
+    for mut group in groups {
+        group.shuffle(&mut rng);
+        let test_len = (group.len() as f64 * test).round() as usize;
+        let validation_len =
+            ((group.len() as f64 * validation).round() as usize).min(group.len() - test_len);
+
+        parts[2].extend(group.drain(..test_len));
+        parts[1].extend(group.drain(..validation_len));
+        parts[0].extend(group);
+    }
+
+    // Interleave the classes again.
+    for part in parts.iter_mut() {
+        part.shuffle(&mut rng);
+    }
+    parts
+}
+
+// Random train / validation / test split, `validation` and `test` are the
+// fractions of samples held out.
+pub fn train_validation_test_split(
+    inputs: &[Vec<f64>],
+    targets: &[Vec<f64>],
+    validation: f64,
+    test: f64,
+    seed: u64,
+) -> Split {
+    check_lengths(inputs, targets);
+    check_fractions(validation, test);
+
+    let [train, validation, test] =
+        partition(vec![(0..inputs.len()).collect()], validation, test, seed);
+    Split {
+        train: select(inputs, targets, &train),
+        validation: select(inputs, targets, &validation),
+        test: select(inputs, targets, &test),
+    }
+}
+
+// Like `train_validation_test_split` but keeps the class proportions of the
+// targets (decoded with `metrics::class_index`) in every partition.
+pub fn stratified_split(
+    inputs: &[Vec<f64>],
+    targets: &[Vec<f64>],
+    validation: f64,
+    test: f64,
+    seed: u64,
+) -> Split {
+    check_lengths(inputs, targets);
+    check_fractions(validation, test);
+
+    let [train, validation, test] = partition(classes(targets), validation, test, seed);
+    Split {
+        train: select(inputs, targets, &train),
+        validation: select(inputs, targets, &validation),
+        test: select(inputs, targets, &test),
+    }
+}
+
+fn folds_from(assignment: Vec<Vec<usize>>) -> Vec<Fold> {
+    (0..assignment.len())
+        .map(|k| {
+            let train = assignment
+                .iter()
+                .enumerate()
+                .filter(|(fold, _)| *fold != k)
+                .flat_map(|(_, indices)| indices.iter().copied())
+                .collect();
+            (train, assignment[k].clone())
+        })
+        .collect()
+}
+
+// Splits `len` shuffled samples into `k` folds of (nearly) equal size.
+pub fn k_fold(len: usize, k: usize, seed: u64) -> Vec<Fold> {
+    if k < 2 || k > len {
+        panic!("Cannot make {} folds out of {} samples", k, len);
+    }
+
+    let mut order: Vec<usize> = (0..len).collect();
+    order.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let mut assignment = vec![vec![]; k];
+    for (position, index) in order.into_iter().enumerate() {
+        assignment[position % k].push(index);
+    }
+    folds_from(assignment)
+}
+
+// K folds with the class proportions of the targets.
+pub fn stratified_k_fold(targets: &[Vec<f64>], k: usize, seed: u64) -> Vec<Fold> {
+    if k < 2 || k > targets.len() {
+        panic!("Cannot make {} folds out of {} samples", k, targets.len());
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut assignment = vec![vec![]; k];
+    // Dealing class after class round-robin keeps fold sizes balanced.
+    let mut position = 0;
+    for mut class in classes(targets) {
+        class.shuffle(&mut rng);
+        for index in class {
+            assignment[position % k].push(index);
+            position += 1;
+        }
+    }
+    folds_from(assignment)
+}
+
+pub struct FoldResult {
+    pub history: History,
+    pub loss: f64,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+impl FoldResult {
+    // `loss` or the name of a trainer metric.
+    pub fn value(&self, name: &str) -> Option<f64> {
+        match name {
+            "loss" => Some(self.loss),
+            _ => self.metrics.get(name).copied(),
+        }
+    }
+}
+
+pub struct CrossValidation {
+    pub folds: Vec<FoldResult>,
+}
+
+impl CrossValidation {
+    pub fn values(&self, name: &str) -> Vec<f64> {
+        self.folds
+            .iter()
+            .filter_map(|fold| fold.value(name))
+            .collect()
+    }
+
+    pub fn mean(&self, name: &str) -> f64 {
+        let values = self.values(name);
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    // Population standard deviation over the folds.
+    pub fn std(&self, name: &str) -> f64 {
+        let values = self.values(name);
+        let mean = self.mean(name);
+        (values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+    }
+
+    // Mean and standard deviation of the loss and of every metric.
+    pub fn summary(&self) -> BTreeMap<String, (f64, f64)> {
+        let mut names = vec!["loss".to_string()];
+        if let Some(fold) = self.folds.first() {
+            names.extend(fold.metrics.keys().cloned());
+        }
+        names
+            .into_iter()
+            .map(|name| {
+                let statistics = (self.mean(&name), self.std(&name));
+                (name, statistics)
+            })
+            .collect()
+    }
+}
+
+// Trains a fresh model on every fold and evaluates it on the held-out part.
+// `setup` builds the model and the trainer (with its metrics) of a fold, so
+// every fold starts from the same configuration.
+pub fn cross_validate<M: Model>(
+    inputs: &[Vec<f64>],
+    targets: &[Vec<f64>],
+    folds: &[Fold],
+    mut setup: impl FnMut(usize) -> (M, Trainer),
+) -> CrossValidation {
+    check_lengths(inputs, targets);
+
+    let folds = folds
+        .iter()
+        .enumerate()
+        .map(|(k, (train, held_out))| {
+            let (mut model, mut trainer) = setup(k);
+            let train = select(inputs, targets, train);
+            let held_out = select(inputs, targets, held_out);
+
+            let history = trainer.fit(&mut model, &train.inputs, &train.targets);
+            let (loss, metrics) = trainer.evaluate(&mut model, &held_out.inputs, &held_out.targets);
+            FoldResult {
+                history,
+                loss,
+                metrics,
+            }
+        })
+        .collect();
+
+    CrossValidation { folds }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::split::{
+        cross_validate, k_fold, stratified_k_fold, stratified_split, train_validation_test_split,
+    };
+    use crate::nn::activations::IDENTITY;
+    use crate::nn::metrics::mean_absolute_error;
+    use crate::nn::network::Network;
+    use crate::nn::trainer::Trainer;
+
+    fn dataset() -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+        let inputs: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64 / 20.0]).collect();
+        // 15 samples of class 0 and 5 of class 1.
+        let targets = (0..20)
+            .map(|i| vec![if i % 4 == 0 { 1.0 } else { 0.0 }])
+            .collect();
+        (inputs, targets)
+    }
+
+    #[test]
+    fn splits_are_disjoint_and_seeded() {
+        let (inputs, targets) = dataset();
+        let split = train_validation_test_split(&inputs, &targets, 0.2, 0.25, 1);
+        assert_eq!(split.train.inputs.len(), 11);
+        assert_eq!(split.validation.inputs.len(), 4);
+        assert_eq!(split.test.inputs.len(), 5);
+
+        let mut all: Vec<f64> = [&split.train, &split.validation, &split.test]
+            .iter()
+            .flat_map(|part| part.inputs.iter().map(|row| row[0]))
+            .collect();
+        all.sort_by(|a, b| a.total_cmp(b));
+        assert_eq!(all, inputs.iter().map(|row| row[0]).collect::<Vec<f64>>());
+
+        let again = train_validation_test_split(&inputs, &targets, 0.2, 0.25, 1);
+        assert_eq!(again.test.inputs, split.test.inputs);
+    }
+
+    #[test]
+    fn stratified_split_keeps_proportions() {
+        let (inputs, targets) = dataset();
+        let split = stratified_split(&inputs, &targets, 0.2, 0.2, 4);
+        let positives = |targets: &[Vec<f64>]| targets.iter().filter(|t| t[0] == 1.0).count();
+
+        assert_eq!(positives(&split.train.targets), 3);
+        assert_eq!(positives(&split.validation.targets), 1);
+        assert_eq!(positives(&split.test.targets), 1);
+        assert_eq!(split.test.targets.len(), 4);
+    }
+
+    #[test]
+    fn folds_cover_every_sample_once() {
+        let (_, targets) = dataset();
+        for folds in [k_fold(20, 3, 0), stratified_k_fold(&targets, 5, 0)] {
+            let mut held_out: Vec<usize> = folds.iter().flat_map(|f| f.1.clone()).collect();
+            held_out.sort();
+            assert_eq!(held_out, (0..20).collect::<Vec<usize>>());
+            for (train, test) in &folds {
+                assert_eq!(train.len() + test.len(), 20);
+                assert!(test.iter().all(|index| !train.contains(index)));
+            }
+        }
+
+        for (_, test) in stratified_k_fold(&targets, 5, 0) {
+            assert_eq!(test.iter().filter(|&&i| targets[i][0] == 1.0).count(), 1);
+        }
+    }
+
+    #[test]
+    fn cross_validation_aggregates_folds() {
+        let inputs: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64 / 20.0]).collect();
+        let targets: Vec<Vec<f64>> = inputs.iter().map(|x| vec![2.0 * x[0] + 1.0]).collect();
+
+        let result = cross_validate(&inputs, &targets, &k_fold(20, 4, 0), |_| {
+            (
+                Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.05),
+                Trainer::new(100, 5).metric("mae", mean_absolute_error),
+            )
+        });
+
+        assert_eq!(result.folds.len(), 4);
+        assert_eq!(result.values("mae").len(), 4);
+        assert!(result
+            .folds
+            .iter()
+            .all(|fold| fold.history.epochs.len() == 100));
+        assert!(result.mean("mae") < 0.1);
+
+        let summary = result.summary();
+        assert_eq!(summary.keys().collect::<Vec<_>>(), vec!["loss", "mae"]);
+        assert!(summary["loss"].1 >= 0.0);
+    }
+}
@@ -0,0 +1,282 @@
+use std::fmt::{Display, Formatter, Result};
+
+use rust_nn::nn::activations::{Activation, IDENTITY, RELU, SIGMOID, TANH};
+use rust_nn::nn::network::Network;
+use rust_nn::nn::siren::Sine;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActivationKind {
+    Identity,
+    Sigmoid,
+    Tanh,
+    Relu,
+    // sin(omega_0 * x) as in SIREN, added as a `Sine` layer.
+    Sine,
+}
+
+impl ActivationKind {
+    pub const ALL: [ActivationKind; 5] = [
+        ActivationKind::Identity,
+        ActivationKind::Sigmoid,
+        ActivationKind::Tanh,
+        ActivationKind::Relu,
+        ActivationKind::Sine,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ActivationKind::Identity => "identity",
+            ActivationKind::Sigmoid => "sigmoid",
+            ActivationKind::Tanh => "tanh",
+            ActivationKind::Relu => "relu",
+            ActivationKind::Sine => "sine",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<ActivationKind> {
+        ActivationKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name.to_lowercase())
+    }
+
+    pub fn next(&self) -> ActivationKind {
+        let index = ActivationKind::ALL
+            .iter()
+            .position(|kind| kind == self)
+            .unwrap();
+        ActivationKind::ALL[(index + 1) % ActivationKind::ALL.len()]
+    }
+
+    // Sine is linear here, the network gets a `Sine` layer after the transform.
+    pub fn activation(&self) -> Activation<'static> {
+        match self {
+            ActivationKind::Identity | ActivationKind::Sine => IDENTITY,
+            ActivationKind::Sigmoid => SIGMOID,
+            ActivationKind::Tanh => TANH,
+            ActivationKind::Relu => RELU,
+        }
+    }
+}
+
+pub const MAX_WIDTH: usize = 512;
+
+// Layer sizes and activations, written `2:identity,15:relu,1:sigmoid`. As in
+// `Network::new`, the activation of a layer is applied to the transform
+// leaving it, so the last one is unused.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Architecture {
+    pub layers: Vec<(usize, ActivationKind)>,
+}
+
+impl Architecture {
+    pub fn parse(spec: &str) -> std::result::Result<Architecture, String> {
+        let layers = spec
+            .split(',')
+            .map(|layer| {
+                let (size, activation) = layer.trim().split_once(':').ok_or_else(|| {
+                    format!("Layer '{}' should be written <size>:<activation>", layer)
+                })?;
+                let size = match size.parse::<usize>() {
+                    Ok(size) if size > 0 => size,
+                    _ => return Err(format!("Invalid size '{}' in layer '{}'", size, layer)),
+                };
+                let activation = ActivationKind::parse(activation).ok_or_else(|| {
+                    format!(
+                        "Unknown activation '{}' in layer '{}', expected one of {}",
+                        activation,
+                        layer,
+                        ActivationKind::ALL.map(|kind| kind.name()).join(", ")
+                    )
+                })?;
+                Ok((size, activation))
+            })
+            .collect::<std::result::Result<Vec<_>, String>>()?;
+
+        if layers.len() < 2 {
+            return Err(format!(
+                "'{}' needs at least an input and an output layer",
+                spec
+            ));
+        }
+        Ok(Architecture { layers })
+    }
+
+    // The architecture the gym starts with.
+    pub fn default_for(inputs: usize, outputs: usize) -> Architecture {
+        Architecture {
+            layers: vec![
+                (inputs, ActivationKind::Identity),
+                (15, ActivationKind::Relu),
+                (15, ActivationKind::Relu),
+                (outputs, ActivationKind::Sigmoid),
+            ],
+        }
+    }
+
+    pub fn is_hidden(&self, index: usize) -> bool {
+        index > 0 && index + 1 < self.layers.len()
+    }
+
+    fn check_hidden(&self, index: usize) {
+        if !self.is_hidden(index) {
+            panic!("Layer {} is not a hidden layer of {}", index, self);
+        }
+    }
+
+    // Inserts a copy of the last hidden layer, or a 15 unit ReLU layer, before
+    // the output layer.
+    pub fn add_hidden(&mut self) {
+        let output = self.layers.len() - 1;
+        let layer = if output > 1 {
+            self.layers[output - 1]
+        } else {
+            (15, ActivationKind::Relu)
+        };
+        self.layers.insert(output, layer);
+    }
+
+    pub fn remove_hidden(&mut self, index: usize) {
+        self.check_hidden(index);
+        self.layers.remove(index);
+    }
+
+    // Changes the width of a hidden layer by `delta`, within 1..=MAX_WIDTH.
+    pub fn resize(&mut self, index: usize, delta: i64) {
+        self.check_hidden(index);
+        let width = self.layers[index].0 as i64 + delta;
+        self.layers[index].0 = width.clamp(1, MAX_WIDTH as i64) as usize;
+    }
+
+    pub fn cycle_activation(&mut self, index: usize) {
+        self.layers[index].1 = self.layers[index].1.next();
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.layers[0].0
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.layers[self.layers.len() - 1].0
+    }
+
+    pub fn has_sine(&self) -> bool {
+        self.layers[..self.layers.len() - 1]
+            .iter()
+            .any(|(_, activation)| *activation == ActivationKind::Sine)
+    }
+
+    // Sine layers use `omega_0` as their frequency factor.
+    pub fn network(&self, learning_rate: f64, omega_0: f64) -> Network<'static> {
+        let mut network = Network::new(
+            self.layers
+                .iter()
+                .map(|(size, activation)| (*size, activation.activation()))
+                .collect(),
+            learning_rate,
+        );
+        for (i, (_, activation)) in self.layers[..self.layers.len() - 1].iter().enumerate() {
+            if *activation == ActivationKind::Sine {
+                network.add_layer(i, Box::new(Sine::new(omega_0)));
+            }
+        }
+        network
+    }
+}
+
+impl Display for Architecture {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let layers: Vec<String> = self
+            .layers
+            .iter()
+            .map(|(size, activation)| format!("{}:{}", size, activation.name()))
+            .collect();
+        write!(f, "{}", layers.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gym::architecture::{ActivationKind, Architecture, MAX_WIDTH};
+
+    #[test]
+    fn parses_and_prints_layers() {
+        let architecture = Architecture::parse("2:identity, 64:ReLU,1:sigmoid").unwrap();
+        assert_eq!(
+            architecture.layers,
+            vec![
+                (2, ActivationKind::Identity),
+                (64, ActivationKind::Relu),
+                (1, ActivationKind::Sigmoid)
+            ]
+        );
+        assert_eq!(architecture.to_string(), "2:identity,64:relu,1:sigmoid");
+        assert_eq!(architecture.network(0.1, 30.0).weights[0].cols, 64);
+    }
+
+    #[test]
+    fn sine_layers_follow_their_transform() {
+        let architecture = Architecture::parse("1:sine,8:identity,1:sine").unwrap();
+        assert!(architecture.has_sine());
+        assert!(!Architecture::parse("1:identity,1:sine").unwrap().has_sine());
+
+        // sin(2 * (w x + b)) with w = 1 and b = 0 on the first transform.
+        let mut network = architecture.network(0.1, 2.0);
+        network.weights[0].data = vec![vec![1.0; 8]];
+        network.biases[0].data = vec![vec![0.0; 8]];
+        network.feed_forward(vec![vec![0.5]]);
+        assert!(network.data[1].data[0]
+            .iter()
+            .all(|x| (x - 1f64.sin()).abs() < 1e-12));
+    }
+
+    #[test]
+    fn edits_hidden_layers() {
+        let mut architecture = Architecture::parse("2:identity,1:sigmoid").unwrap();
+        architecture.add_hidden();
+        architecture.add_hidden();
+        assert_eq!(
+            architecture.to_string(),
+            "2:identity,15:relu,15:relu,1:sigmoid"
+        );
+
+        architecture.resize(1, 10);
+        architecture.resize(2, -100);
+        architecture.cycle_activation(2);
+        architecture.cycle_activation(2);
+        architecture.cycle_activation(0);
+        assert_eq!(
+            architecture.to_string(),
+            "2:sigmoid,25:relu,1:identity,1:sigmoid"
+        );
+
+        architecture.resize(1, 1000);
+        assert_eq!(architecture.layers[1].0, MAX_WIDTH);
+        architecture.remove_hidden(2);
+        assert_eq!(architecture.to_string(), "2:sigmoid,512:relu,1:sigmoid");
+        assert!(!architecture.is_hidden(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "not a hidden layer")]
+    fn keeps_input_and_output_layers() {
+        let mut architecture = Architecture::parse("2:identity,8:relu,1:sigmoid").unwrap();
+        architecture.remove_hidden(0);
+    }
+
+    #[test]
+    fn rejects_invalid_layers() {
+        for (spec, error) in [
+            ("2:identity", "at least"),
+            ("2:identity,64", "<size>:<activation>"),
+            ("2:identity,0:relu", "Invalid size"),
+            ("2:identity,x:relu", "Invalid size"),
+            (
+                "2:identity,8:softplus",
+                "expected one of identity, sigmoid, tanh, relu, sine",
+            ),
+        ] {
+            let message = Architecture::parse(spec).unwrap_err();
+            assert!(message.contains(error), "{}: {}", spec, message);
+        }
+    }
+}
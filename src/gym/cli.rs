@@ -0,0 +1,339 @@
+use std::str::FromStr;
+
+use rust_nn::data::encoding::CoordinateEncoding;
+use rust_nn::image_nn::{Channels, Normalization};
+use rust_nn::nn::optimizer::Optimizer;
+use rust_nn::nn::siren::OMEGA_0;
+
+use super::{architecture::Architecture, headless::Headless};
+
+pub const USAGE: &str = "Usage: rust-nn <image> [options]
+
+Options:
+  --channels MODE          binary (default), grayscale, rgb or rgba targets
+  --normalization RANGE    unit (default) maps channels to [0, 1], symmetric to [-1, 1]
+  --encoding NAME          coordinate encoding: raw (default), fourier:FEATURES:SCALE[:SEED]
+                           or positional:FREQUENCIES
+  --layers SPEC            layer sizes and activations, e.g. 2:identity,64:relu,1:sigmoid;
+                           sine layers make a SIREN, e.g. 2:sine,256:sine,1:identity
+  --omega-0 N              frequency factor of sine layers (default 30)
+  --batch-size N           samples per batch (default 20)
+  --optimizer NAME         sgd (default), momentum or adam
+  --learning-rate RATE     initial learning rate (default 0.000001)
+  --epochs-per-frame N     epochs trained between two frames (default 7)
+  --seed N                 seed of the weight initialization
+  --scale N                on-screen size of an image pixel (default 8)
+  --model FILE             network loaded at start if it exists, saved and loaded with
+                           S and L in the window and written in headless mode
+  --headless               train without a window, then write the image and the model
+  --epochs N               headless epoch budget (default 10000)
+  --target-loss LOSS       headless: stop once the loss is at most LOSS
+  --log-every N            headless: epochs between progress lines (default 100)
+  --output FILE            headless: reconstructed image (default learned.png)
+  --help                   print this message";
+
+pub struct Options {
+    pub image: String,
+    pub channels: Channels,
+    pub normalization: Normalization,
+    pub encoding: CoordinateEncoding,
+    // None keeps the default architecture for the image.
+    pub layers: Option<Architecture>,
+    pub omega_0: f64,
+    pub batch_size: usize,
+    pub optimizer: Optimizer,
+    pub learning_rate: f64,
+    pub epochs_per_frame: usize,
+    pub seed: Option<u64>,
+    pub scale: f32,
+    pub model: Option<String>,
+    pub headless: Option<Headless>,
+}
+
+const HEADLESS_OPTIONS: [&str; 4] = ["--epochs", "--target-loss", "--log-every", "--output"];
+
+// Positive and finite, floats would otherwise accept `inf`.
+fn number<T: FromStr + PartialOrd + Default>(name: &str, value: &str) -> Result<T, String> {
+    let finite = value.parse::<f64>().is_ok_and(f64::is_finite);
+    match value.parse::<T>() {
+        Ok(number) if finite && number > T::default() => Ok(number),
+        _ => Err(format!(
+            "{} expects a positive number, got '{}'",
+            name, value
+        )),
+    }
+}
+
+fn channels(value: &str) -> Result<Channels, String> {
+    match value {
+        "binary" => Ok(Channels::Binary),
+        "grayscale" => Ok(Channels::Grayscale),
+        "rgb" => Ok(Channels::Rgb),
+        "rgba" => Ok(Channels::Rgba),
+        _ => Err(format!(
+            "Unknown color mode '{}', expected binary, grayscale, rgb or rgba",
+            value
+        )),
+    }
+}
+
+fn normalization(value: &str) -> Result<Normalization, String> {
+    match value {
+        "unit" => Ok(Normalization::Unit),
+        "symmetric" => Ok(Normalization::Symmetric),
+        _ => Err(format!(
+            "Unknown normalization '{}', expected unit or symmetric",
+            value
+        )),
+    }
+}
+
+fn encoding(value: &str) -> Result<CoordinateEncoding, String> {
+    let parts: Vec<&str> = value.split(':').collect();
+    match parts[..] {
+        ["raw"] => Ok(CoordinateEncoding::Raw),
+        ["fourier", features, scale] | ["fourier", features, scale, _] => {
+            let seed = match parts.get(3) {
+                Some(seed) => seed
+                    .parse()
+                    .map_err(|_| format!("Fourier seed expects an integer, got '{}'", seed))?,
+                None => 0,
+            };
+            Ok(CoordinateEncoding::Fourier {
+                features: number("Fourier features", features)?,
+                scale: number("Fourier scale", scale)?,
+                seed,
+            })
+        }
+        ["positional", frequencies] => Ok(CoordinateEncoding::Positional {
+            frequencies: number("Positional frequencies", frequencies)?,
+        }),
+        _ => Err(format!(
+            "Unknown encoding '{}', expected raw, fourier:FEATURES:SCALE[:SEED] \
+             or positional:FREQUENCIES",
+            value
+        )),
+    }
+}
+
+fn optimizer(value: &str) -> Result<Optimizer, String> {
+    match value {
+        "sgd" => Ok(Optimizer::Sgd),
+        "momentum" => Ok(Optimizer::momentum()),
+        "adam" => Ok(Optimizer::adam()),
+        _ => Err(format!(
+            "Unknown optimizer '{}', expected sgd, momentum or adam",
+            value
+        )),
+    }
+}
+
+// Options are accepted as `--name value` or `--name=value`. A second
+// positional argument is read as the color mode, like `--channels`.
+pub fn parse(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        image: String::new(),
+        channels: Channels::Binary,
+        normalization: Normalization::Unit,
+        encoding: CoordinateEncoding::Raw,
+        layers: None,
+        omega_0: OMEGA_0,
+        batch_size: 20,
+        optimizer: Optimizer::Sgd,
+        learning_rate: 0.000001,
+        epochs_per_frame: 7,
+        seed: None,
+        scale: 8.0,
+        model: None,
+        headless: None,
+    };
+    let mut headless = Headless::default();
+    let mut is_headless = false;
+    let mut headless_option = None;
+    let mut positional = vec![];
+
+    let mut rest = args.iter().skip(1);
+    while let Some(arg) = rest.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg.as_str());
+            continue;
+        }
+        if arg == "--headless" {
+            is_headless = true;
+            continue;
+        }
+
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, value),
+            None => (
+                arg.as_str(),
+                rest.next()
+                    .ok_or_else(|| format!("{} needs a value", arg))?
+                    .as_str(),
+            ),
+        };
+        if HEADLESS_OPTIONS.contains(&name) {
+            headless_option = Some(name);
+        }
+        match name {
+            "--channels" => options.channels = channels(value)?,
+            "--normalization" => options.normalization = normalization(value)?,
+            "--encoding" => options.encoding = encoding(value)?,
+            "--layers" => options.layers = Some(Architecture::parse(value)?),
+            "--omega-0" => options.omega_0 = number(name, value)?,
+            "--batch-size" => options.batch_size = number(name, value)?,
+            "--optimizer" => options.optimizer = optimizer(value)?,
+            "--learning-rate" => options.learning_rate = number(name, value)?,
+            "--epochs-per-frame" => options.epochs_per_frame = number(name, value)?,
+            "--seed" => {
+                options.seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("--seed expects an integer, got '{}'", value))?,
+                )
+            }
+            "--scale" => options.scale = number(name, value)?,
+            "--model" => options.model = Some(value.to_string()),
+            "--epochs" => headless.epochs = number(name, value)?,
+            "--target-loss" => headless.target_loss = Some(number(name, value)?),
+            "--log-every" => headless.log_every = number(name, value)?,
+            "--output" => headless.output = value.to_string(),
+            _ => return Err(format!("Unknown option {}", name)),
+        }
+    }
+
+    match positional[..] {
+        [] => return Err("Image path argument not found".to_string()),
+        [image] => options.image = image.to_string(),
+        [image, mode] => {
+            options.image = image.to_string();
+            options.channels = channels(mode)?;
+        }
+        _ => {
+            return Err(format!(
+                "Unexpected argument '{}'",
+                positional[2..].join(" ")
+            ))
+        }
+    }
+
+    if is_headless {
+        if let Some(model) = &options.model {
+            headless.model = model.clone();
+        }
+        options.headless = Some(headless);
+    } else if let Some(name) = headless_option {
+        return Err(format!("{} only applies with --headless", name));
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_nn::data::encoding::CoordinateEncoding;
+    use rust_nn::image_nn::{Channels, Normalization};
+    use rust_nn::nn::optimizer::Optimizer;
+
+    use crate::gym::cli::{parse, Options};
+
+    fn run(line: &str) -> Result<Options, String> {
+        let args: Vec<String> = format!("rust-nn {}", line)
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        parse(&args)
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = run(
+            "image.png rgb --layers 2:identity,64:relu,3:sigmoid --batch-size=32 \
+             --optimizer adam --learning-rate 0.001 --seed 7 --scale 4",
+        )
+        .unwrap();
+        assert_eq!(options.image, "image.png");
+        assert_eq!(options.channels, Channels::Rgb);
+        assert_eq!(
+            options.layers.unwrap().to_string(),
+            "2:identity,64:relu,3:sigmoid"
+        );
+        assert_eq!(options.batch_size, 32);
+        assert_eq!(options.optimizer, Optimizer::adam());
+        assert_eq!(options.learning_rate, 0.001);
+        assert_eq!(options.epochs_per_frame, 7);
+        assert_eq!(options.seed, Some(7));
+        assert_eq!(options.scale, 4.0);
+        assert_eq!(options.normalization, Normalization::Unit);
+        assert_eq!(options.encoding, CoordinateEncoding::Raw);
+        assert!(options.headless.is_none());
+
+        let options =
+            run("image.png --encoding fourier:64:10:3 --normalization symmetric").unwrap();
+        assert_eq!(
+            options.encoding,
+            CoordinateEncoding::Fourier {
+                features: 64,
+                scale: 10.0,
+                seed: 3
+            }
+        );
+        assert_eq!(options.normalization, Normalization::Symmetric);
+        let options = run("image.png --layers 2:sine,32:identity,1:identity --omega-0 10").unwrap();
+        assert!(options.layers.unwrap().has_sine());
+        assert_eq!(options.omega_0, 10.0);
+        let options = run("image.png --encoding=positional:6").unwrap();
+        assert_eq!(
+            options.encoding,
+            CoordinateEncoding::Positional { frequencies: 6 }
+        );
+
+        let options =
+            run("image.png --headless --epochs 50 --target-loss 0.01 --model net.json").unwrap();
+        let headless = options.headless.unwrap();
+        assert_eq!(headless.epochs, 50);
+        assert_eq!(headless.target_loss, Some(0.01));
+        assert_eq!(headless.model, "net.json");
+    }
+
+    #[test]
+    fn reports_invalid_options() {
+        for (line, error) in [
+            ("", "Image path"),
+            (
+                "image.png --batch-size 0",
+                "--batch-size expects a positive number, got '0'",
+            ),
+            ("image.png --learning-rate fast", "got 'fast'"),
+            ("image.png --learning-rate inf", "got 'inf'"),
+            ("image.png --scale NaN", "got 'NaN'"),
+            (
+                "image.png --optimizer rmsprop",
+                "expected sgd, momentum or adam",
+            ),
+            ("image.png --layers 2:relu", "at least"),
+            ("image.png --channels cmyk", "Unknown color mode 'cmyk'"),
+            (
+                "image.png --normalization signed",
+                "expected unit or symmetric",
+            ),
+            ("image.png --encoding fourier:64", "Unknown encoding"),
+            (
+                "image.png --encoding fourier:0:10",
+                "Fourier features expects a positive number, got '0'",
+            ),
+            ("image.png --encoding positional:x", "got 'x'"),
+            (
+                "image.png --epochs 5",
+                "--epochs only applies with --headless",
+            ),
+            ("image.png --seed", "--seed needs a value"),
+            ("image.png --speed 2", "Unknown option --speed"),
+            ("image.png rgb extra", "Unexpected argument 'extra'"),
+        ] {
+            match run(line) {
+                Ok(_) => panic!("{} was accepted", line),
+                Err(message) => assert!(message.contains(error), "{}: {}", line, message),
+            }
+        }
+    }
+}
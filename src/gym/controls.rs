@@ -0,0 +1,200 @@
+use std::{fs, io::Write};
+
+use image::{imageops, RgbaImage};
+use macroquad::prelude::*;
+use serde_json::Value;
+
+use rust_nn::image_nn;
+use rust_nn::nn::network::Network;
+
+use super::ui::{button, FONT_SIZE};
+
+pub const SCREENSHOT_FILE: &str = "screenshot.png";
+pub const LOSS_FILE: &str = "loss_history.csv";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Save,
+    Load,
+    Screenshot,
+    ExportLoss,
+}
+
+// Training controls: P pauses or resumes, N trains a single epoch, S saves
+// and L loads the network, C takes a screenshot and X exports the loss
+// history. Each one also has a button.
+pub struct Controls {
+    pub paused: bool,
+    step: bool,
+    // Outcome of the last action, shown under the buttons.
+    pub status: String,
+}
+
+impl Controls {
+    pub fn new() -> Controls {
+        Controls {
+            paused: false,
+            step: false,
+            status: String::new(),
+        }
+    }
+
+    // Epochs to train this frame.
+    pub fn epochs(&mut self, epochs_per_frame: usize) -> usize {
+        if !self.paused {
+            epochs_per_frame
+        } else if self.step {
+            self.step = false;
+            1
+        } else {
+            0
+        }
+    }
+
+    // Buttons wrap to a new row past `width`.
+    pub fn update(&mut self, x: f32, y: f32, width: f32) -> Option<Action> {
+        let pause = if self.paused {
+            "resume (P)"
+        } else {
+            "pause (P)"
+        };
+        let buttons = [
+            (pause, KeyCode::P, 95.0),
+            ("step (N)", KeyCode::N, 80.0),
+            ("save (S)", KeyCode::S, 80.0),
+            ("load (L)", KeyCode::L, 80.0),
+            ("screenshot (C)", KeyCode::C, 125.0),
+            ("loss csv (X)", KeyCode::X, 110.0),
+        ];
+
+        let mut pressed = None;
+        let (mut left, mut top) = (x, y);
+        for (index, (label, key, button_width)) in buttons.into_iter().enumerate() {
+            if left + button_width > x + width && left > x {
+                left = x;
+                top += 28.0;
+            }
+            if button(label, left, top, button_width, 22.0) || is_key_pressed(key) {
+                pressed = Some(index);
+            }
+            left += button_width + 6.0;
+        }
+        draw_text(&self.status, x, top + 42.0, FONT_SIZE, LIGHTGRAY);
+
+        match pressed? {
+            0 => {
+                self.paused = !self.paused;
+                None
+            }
+            1 => {
+                self.paused = true;
+                self.step = true;
+                None
+            }
+            2 => Some(Action::Save),
+            3 => Some(Action::Load),
+            4 => Some(Action::Screenshot),
+            _ => Some(Action::ExportLoss),
+        }
+    }
+}
+
+// Writes `epoch,loss` lines, epochs counted from 1.
+pub fn export_loss(history: &[f64], path: &str) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    writeln!(file, "epoch,loss")?;
+    for (epoch, loss) in history.iter().enumerate() {
+        writeln!(file, "{},{}", epoch + 1, loss)?;
+    }
+    Ok(())
+}
+
+// Saves what has been drawn so far this frame.
+pub fn screenshot(path: &str) -> image::ImageResult<()> {
+    let screen = get_screen_data();
+    let image = RgbaImage::from_raw(screen.width as u32, screen.height as u32, screen.bytes)
+        .expect("Screen data does not match its size");
+    // The framebuffer is read bottom row first.
+    image_nn::save_png(&imageops::flip_vertical(&image), path)
+}
+
+// Checks that a file written by `Network::save` fits `network`, since loading
+// a different architecture would panic.
+pub fn check_save_file(path: &str, network: &Network) -> Result<(), String> {
+    let contents =
+        fs::read_to_string(path).map_err(|error| format!("Unable to read {}: {}", path, error))?;
+    let save: Value = serde_json::from_str(&contents)
+        .map_err(|error| format!("{} is not a saved network: {}", path, error))?;
+
+    let shapes: Vec<(usize, usize)> = save["weights"]
+        .as_array()
+        .ok_or_else(|| format!("{} has no weights", path))?
+        .iter()
+        .map(|matrix| {
+            let rows = matrix.as_array().map_or(0, |rows| rows.len());
+            let cols = matrix[0].as_array().map_or(0, |cols| cols.len());
+            (rows, cols)
+        })
+        .collect();
+    let expected: Vec<(usize, usize)> = network
+        .weights
+        .iter()
+        .map(|weights| (weights.rows, weights.cols))
+        .collect();
+
+    if shapes != expected {
+        return Err(format!(
+            "{} has weights of shapes {:?}, the network needs {:?}",
+            path, shapes, expected
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rust_nn::nn::activations::{IDENTITY, RELU, SIGMOID};
+    use rust_nn::nn::network::Network;
+
+    use crate::gym::controls::{check_save_file, export_loss, Controls};
+
+    #[test]
+    fn steps_only_while_paused() {
+        let mut controls = Controls::new();
+        assert_eq!(controls.epochs(7), 7);
+        controls.paused = true;
+        assert_eq!(controls.epochs(7), 0);
+        controls.step = true;
+        assert_eq!(controls.epochs(7), 1);
+        assert_eq!(controls.epochs(7), 0);
+    }
+
+    #[test]
+    fn exports_loss_history() {
+        let path = std::env::temp_dir().join("rust_nn_loss_history.csv");
+        let path = path.to_str().unwrap();
+        export_loss(&[0.5, 0.25], path).unwrap();
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "epoch,loss\n1,0.5\n2,0.25\n"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn checks_saved_architecture() {
+        let path = std::env::temp_dir().join("rust_nn_controls_model.json");
+        let path = path.to_str().unwrap();
+        let network = Network::new(vec![(2, IDENTITY), (4, RELU), (1, SIGMOID)], 0.1);
+        network.save(path.to_string());
+
+        assert!(check_save_file(path, &network).is_ok());
+        let other = Network::new(vec![(2, IDENTITY), (5, RELU), (1, SIGMOID)], 0.1);
+        let message = check_save_file(path, &other).unwrap_err();
+        assert!(message.contains("[(2, 4), (4, 1)]"), "{}", message);
+        assert!(check_save_file("missing.json", &network).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
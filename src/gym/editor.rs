@@ -0,0 +1,120 @@
+use macroquad::prelude::*;
+
+use super::{
+    architecture::Architecture,
+    ui::{button, FONT_SIZE, PANEL_COLOR},
+};
+
+const ROW_HEIGHT: f32 = 26.0;
+
+// Panel editing a draft of the architecture, toggled with Tab. The network is
+// only rebuilt when the draft is applied.
+pub struct Editor {
+    pub open: bool,
+    pub draft: Architecture,
+}
+
+impl Editor {
+    pub fn new(architecture: &Architecture) -> Editor {
+        Editor {
+            open: false,
+            draft: architecture.clone(),
+        }
+    }
+
+    // Handles the panel for this frame, returns the architecture to rebuild
+    // the network with once a changed draft is applied.
+    pub fn update(
+        &mut self,
+        current: &Architecture,
+        x: f32,
+        y: f32,
+        width: f32,
+    ) -> Option<Architecture> {
+        if is_key_pressed(KeyCode::Tab) {
+            self.open = !self.open;
+            if self.open {
+                self.draft = current.clone();
+            }
+        }
+        if !self.open {
+            return None;
+        }
+
+        let layers = self.draft.layers.len();
+        let height = ROW_HEIGHT * (layers as f32 + 3.0) + 10.0;
+        draw_rectangle(x, y, width, height, PANEL_COLOR);
+        draw_text(
+            "LAYERS (Tab to close, Shift+click for steps of 10)",
+            x + 10.0,
+            y + 20.0,
+            FONT_SIZE,
+            LIGHTGRAY,
+        );
+
+        let step = if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+            10
+        } else {
+            1
+        };
+        let mut removed = None;
+        for index in 0..layers {
+            let row = y + ROW_HEIGHT * (index as f32 + 1.0) + 5.0;
+            let (size, activation) = self.draft.layers[index];
+            let name = if index == 0 {
+                "input"
+            } else if index + 1 == layers {
+                "output"
+            } else {
+                "hidden"
+            };
+            draw_text(
+                &format!("{} {}", name, size),
+                x + 10.0,
+                row + 18.0,
+                FONT_SIZE,
+                WHITE,
+            );
+
+            // The activation of the output layer is never applied.
+            if index + 1 < layers && button(activation.name(), x + 130.0, row, 90.0, 22.0) {
+                self.draft.cycle_activation(index);
+            }
+            if self.draft.is_hidden(index) {
+                if button("-", x + 230.0, row, 22.0, 22.0) {
+                    self.draft.resize(index, -step);
+                }
+                if button("+", x + 256.0, row, 22.0, 22.0) {
+                    self.draft.resize(index, step);
+                }
+                if button("remove", x + 288.0, row, 70.0, 22.0) {
+                    removed = Some(index);
+                }
+            }
+        }
+        if let Some(index) = removed {
+            self.draft.remove_hidden(index);
+        }
+
+        let row = y + ROW_HEIGHT * (layers as f32 + 1.0) + 10.0;
+        if button("add layer", x + 10.0, row, 100.0, 22.0) {
+            self.draft.add_hidden();
+        }
+        if button("revert", x + 120.0, row, 80.0, 22.0) {
+            self.draft = current.clone();
+        }
+        let changed = self.draft != *current;
+        let apply = button(
+            if changed { "rebuild *" } else { "rebuild" },
+            x + 210.0,
+            row,
+            100.0,
+            22.0,
+        );
+        // Rebuilding resets the training, so an unchanged draft is ignored.
+        if changed && (apply || is_key_pressed(KeyCode::Enter)) {
+            return Some(self.draft.clone());
+        }
+        None
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Instant;
+
+use rust_nn::image_nn::{self, ImageData};
+use rust_nn::nn::network::Network;
+
+pub const SUCCESS: i32 = 0;
+// Training diverged, stopped above the target loss or an output could not
+// be written.
+pub const FAILURE: i32 = 1;
+
+pub struct Headless {
+    pub epochs: usize,
+    // Stops as soon as the epoch loss is at most this value.
+    pub target_loss: Option<f64>,
+    pub log_every: usize,
+    pub output: String,
+    pub model: String,
+}
+
+impl Default for Headless {
+    fn default() -> Headless {
+        Headless {
+            epochs: 10000,
+            target_loss: None,
+            log_every: 100,
+            output: "learned.png".to_string(),
+            model: "model.json".to_string(),
+        }
+    }
+}
+
+// Trains without a window, then writes the reconstructed image and the
+// network. Returns the process exit code.
+pub fn run(
+    options: &Headless,
+    image: &ImageData,
+    network: &mut Network,
+    inputs_batches: &[Vec<Vec<f64>>],
+    targets_batches: &[Vec<Vec<f64>>],
+    learning_rate: f64,
+) -> i32 {
+    let start = Instant::now();
+    let mut loss = f64::INFINITY;
+    let mut epoch = 0;
+
+    while epoch < options.epochs {
+        epoch += 1;
+        loss = network.train_one_epoch(inputs_batches, targets_batches, learning_rate);
+
+        if let Some(non_finite) = network.non_finite() {
+            eprintln!("[ERROR] Epoch {}: {}", epoch, non_finite);
+            return FAILURE;
+        }
+        if epoch % options.log_every == 0 || epoch == options.epochs {
+            println!(
+                "epoch {:>6}: loss {:.9} ({:.1}s)",
+                epoch,
+                loss,
+                start.elapsed().as_secs_f64()
+            );
+        }
+        if options.target_loss.is_some_and(|target| loss <= target) {
+            println!("epoch {:>6}: reached target loss with {:.9}", epoch, loss);
+            break;
+        }
+    }
+
+    let rendered = image.render(network, image.width, image.height);
+    if let Err(error) = image_nn::save_png(&rendered, &options.output) {
+        eprintln!("[ERROR] Unable to write {}: {}", options.output, error);
+        return FAILURE;
+    }
+    if let Err(error) = network.try_save(&options.model) {
+        eprintln!("[ERROR] Unable to write {}: {}", options.model, error);
+        return FAILURE;
+    }
+    println!(
+        "Saved the learned image to {} and the network to {}",
+        options.output, options.model
+    );
+
+    match options.target_loss {
+        Some(target) if loss > target => {
+            eprintln!(
+                "[ERROR] Loss {:.9} is above the target {} after {} epochs",
+                loss, target, epoch
+            );
+            FAILURE
+        }
+        _ => SUCCESS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rust_nn::image_nn::{self, ImageOptions};
+    use rust_nn::nn::activations::{IDENTITY, SIGMOID};
+    use rust_nn::nn::network::Network;
+
+    use crate::gym::headless::{run, Headless, FAILURE, SUCCESS};
+
+    #[test]
+    fn write_errors_fail_the_run() {
+        let image =
+            image_nn::load_image_with("assets/mnist_2.png", ImageOptions::default()).unwrap();
+        let batches = [image.inputs.clone()];
+        let targets = [image.targets.clone()];
+        let mut network = Network::new(vec![(2, IDENTITY), (1, SIGMOID)], 0.1);
+        let directory =
+            std::env::temp_dir().join(format!("rust_nn_headless_{}", std::process::id()));
+        let output = directory.join("learned.png");
+        let model = directory.join("model.json");
+        let mut options = Headless {
+            epochs: 1,
+            output: output.to_str().unwrap().to_string(),
+            model: model.to_str().unwrap().to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            run(&options, &image, &mut network, &batches, &targets, 0.1),
+            FAILURE
+        );
+
+        fs::create_dir_all(&directory).unwrap();
+        assert_eq!(
+            run(&options, &image, &mut network, &batches, &targets, 0.1),
+            SUCCESS
+        );
+        assert!(output.exists() && model.exists());
+
+        options.model = directory
+            .join("missing")
+            .join("model.json")
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            run(&options, &image, &mut network, &batches, &targets, 0.1),
+            FAILURE
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
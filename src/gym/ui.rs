@@ -0,0 +1,25 @@
+use macroquad::prelude::*;
+
+pub const FONT_SIZE: f32 = 20.0;
+pub const PANEL_COLOR: Color = Color::new(0.12, 0.12, 0.12, 0.95);
+
+pub fn is_hovered(x: f32, y: f32, width: f32, height: f32) -> bool {
+    let (mouse_x, mouse_y) = mouse_position();
+    mouse_x >= x && mouse_x <= x + width && mouse_y >= y && mouse_y <= y + height
+}
+
+// Draws a button and returns whether it was clicked this frame.
+pub fn button(label: &str, x: f32, y: f32, width: f32, height: f32) -> bool {
+    let hovered = is_hovered(x, y, width, height);
+    let background = if hovered { GRAY } else { DARKGRAY };
+    draw_rectangle(x, y, width, height, background);
+    let size = measure_text(label, None, FONT_SIZE as u16, 1.0);
+    draw_text(
+        label,
+        x + (width - size.width) / 2.0,
+        y + (height + size.height) / 2.0,
+        FONT_SIZE,
+        WHITE,
+    );
+    hovered && is_mouse_button_pressed(MouseButton::Left)
+}
@@ -0,0 +1,251 @@
+use macroquad::prelude::*;
+
+use rust_nn::nn::network::Network;
+
+use super::ui::{FONT_SIZE, PANEL_COLOR};
+
+// Neurons drawn per layer, wider layers show their first ones.
+const MAX_NEURONS: usize = 16;
+const BINS: usize = 20;
+const POSITIVE: Color = Color::new(0.3, 0.6, 1.0, 1.0);
+const NEGATIVE: Color = Color::new(1.0, 0.35, 0.3, 1.0);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    // Non-finite values are skipped. Equal values get a unit wide range
+    // around them.
+    pub fn new(values: &[f64], bins: usize) -> Histogram {
+        let finite: Vec<f64> = values.iter().copied().filter(|x| x.is_finite()).collect();
+        let mut min = finite.iter().copied().fold(f64::INFINITY, f64::min);
+        let mut max = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if finite.is_empty() {
+            (min, max) = (0.0, 0.0);
+        }
+        if min == max {
+            (min, max) = (min - 0.5, max + 0.5);
+        }
+
+        let mut counts = vec![0; bins];
+        for value in finite {
+            let bin = ((value - min) / (max - min) * bins as f64) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        Histogram { min, max, counts }
+    }
+
+    pub fn draw(&self, label: &str, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        draw_rectangle_lines(x, y, width, height, 1.0, DARKGRAY);
+        let highest = self.counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let bar_width = width / self.counts.len() as f32;
+        for (i, &count) in self.counts.iter().enumerate() {
+            let bar_height = count as f32 / highest * (height - 2.0);
+            draw_rectangle(
+                x + i as f32 * bar_width,
+                y + height - bar_height,
+                (bar_width - 1.0).max(1.0),
+                bar_height,
+                color,
+            );
+        }
+        draw_text(label, x + 3.0, y + 14.0, 16.0, WHITE);
+        draw_text(
+            &format!("{:.2}..{:.2}", self.min, self.max),
+            x + 3.0,
+            y + height + 13.0,
+            14.0,
+            LIGHTGRAY,
+        );
+    }
+}
+
+// Panel showing the network as a graph, with connections colored by weight
+// sign and drawn thicker and more opaque the larger the weight, above
+// histograms of the weights and activations of every transform. Toggled
+// with V.
+pub struct Visualization {
+    pub open: bool,
+    // Outputs of every transform seen since the last draw.
+    activations: Vec<Vec<f64>>,
+}
+
+impl Visualization {
+    pub fn new() -> Visualization {
+        Visualization {
+            open: false,
+            activations: vec![],
+        }
+    }
+
+    // Collects the activations of the last forward pass of `network`.
+    pub fn record(&mut self, network: &Network) {
+        if !self.open {
+            return;
+        }
+        let transforms = network.data.len().saturating_sub(1);
+        self.activations.resize(transforms, vec![]);
+        for (values, output) in self.activations.iter_mut().zip(network.data.iter().skip(1)) {
+            values.extend(output.data.iter().flatten());
+        }
+    }
+
+    pub fn update(&mut self, network: &Network, x: f32, y: f32, width: f32, height: f32) {
+        if is_key_pressed(KeyCode::V) {
+            self.open = !self.open;
+            self.activations = vec![];
+        }
+        if !self.open {
+            return;
+        }
+
+        draw_rectangle(x, y, width, height, PANEL_COLOR);
+        draw_text(
+            "NETWORK (V to close)",
+            x + 10.0,
+            y + 20.0,
+            FONT_SIZE,
+            LIGHTGRAY,
+        );
+        let graph_height = height * 0.55;
+        self.draw_graph(
+            network,
+            x + 10.0,
+            y + 35.0,
+            width - 20.0,
+            graph_height - 40.0,
+        );
+        self.draw_histograms(
+            network,
+            x + 10.0,
+            y + graph_height,
+            width - 20.0,
+            height - graph_height - 10.0,
+        );
+        self.activations = vec![];
+    }
+
+    fn draw_graph(&self, network: &Network, x: f32, y: f32, width: f32, height: f32) {
+        let transforms = network.weights.len();
+        let mut sizes: Vec<usize> = network.weights.iter().map(|w| w.rows).collect();
+        sizes.push(network.weights[transforms - 1].cols);
+
+        let column = |layer: usize| x + width * layer as f32 / transforms as f32;
+        let row = |layer: usize, neuron: usize| {
+            let shown = sizes[layer].min(MAX_NEURONS);
+            y + height * (neuron as f32 + 0.5) / shown as f32
+        };
+
+        for (i, weights) in network.weights.iter().enumerate() {
+            let largest = weights
+                .data
+                .iter()
+                .flatten()
+                .fold(0.0f64, |largest, w| largest.max(w.abs()))
+                .max(f64::MIN_POSITIVE);
+            for a in 0..sizes[i].min(MAX_NEURONS) {
+                for b in 0..sizes[i + 1].min(MAX_NEURONS) {
+                    let weight = weights.data[a][b];
+                    let strength = (weight.abs() / largest) as f32;
+                    let color = if weight >= 0.0 { POSITIVE } else { NEGATIVE };
+                    draw_line(
+                        column(i),
+                        row(i, a),
+                        column(i + 1),
+                        row(i + 1, b),
+                        0.5 + 2.5 * strength,
+                        Color::new(color.r, color.g, color.b, 0.1 + 0.9 * strength),
+                    );
+                }
+            }
+        }
+
+        for (layer, &size) in sizes.iter().enumerate() {
+            // Nodes are shaded by their mean absolute activation in the layer.
+            let means: Vec<f64> = (0..size.min(MAX_NEURONS))
+                .map(|neuron| match network.data.get(layer) {
+                    Some(values) if values.cols == size => {
+                        values.data.iter().map(|row| row[neuron].abs()).sum::<f64>()
+                            / values.rows as f64
+                    }
+                    _ => 0.0,
+                })
+                .collect();
+            let largest = means
+                .iter()
+                .copied()
+                .fold(0.0, f64::max)
+                .max(f64::MIN_POSITIVE);
+            for (neuron, mean) in means.iter().enumerate() {
+                let shade = 0.2 + 0.8 * (mean / largest) as f32;
+                draw_circle(
+                    column(layer),
+                    row(layer, neuron),
+                    5.0,
+                    Color::new(shade, shade, shade, 1.0),
+                );
+            }
+            if size > MAX_NEURONS {
+                draw_text(
+                    &format!("+{}", size - MAX_NEURONS),
+                    column(layer) - 10.0,
+                    y + height + 14.0,
+                    14.0,
+                    LIGHTGRAY,
+                );
+            }
+        }
+    }
+
+    fn draw_histograms(&self, network: &Network, x: f32, y: f32, width: f32, height: f32) {
+        let transforms = network.weights.len();
+        let cell_width = width / transforms as f32;
+        let cell_height = height / 2.0 - 16.0;
+        for (i, weights) in network.weights.iter().enumerate() {
+            let values: Vec<f64> = weights.data.iter().flatten().copied().collect();
+            Histogram::new(&values, BINS).draw(
+                &format!("weights {}", i),
+                x + i as f32 * cell_width,
+                y,
+                cell_width - 8.0,
+                cell_height,
+                POSITIVE,
+            );
+
+            let activations = self
+                .activations
+                .get(i)
+                .map_or(&[][..], |values| &values[..]);
+            Histogram::new(activations, BINS).draw(
+                &format!("activations {}", i),
+                x + i as f32 * cell_width,
+                y + height / 2.0,
+                cell_width - 8.0,
+                cell_height,
+                ORANGE,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gym::visualization::Histogram;
+
+    #[test]
+    fn histogram_counts_values() {
+        let histogram = Histogram::new(&[0.0, 0.1, 0.5, 0.9, 1.0, f64::NAN], 2);
+        assert_eq!((histogram.min, histogram.max), (0.0, 1.0));
+        assert_eq!(histogram.counts, vec![2, 3]);
+
+        let constant = Histogram::new(&[2.0, 2.0], 4);
+        assert_eq!((constant.min, constant.max), (1.5, 2.5));
+        assert_eq!(constant.counts, vec![0, 0, 2, 0]);
+
+        assert_eq!(Histogram::new(&[], 3).counts, vec![0, 0, 0]);
+    }
+}
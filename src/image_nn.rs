@@ -1,4 +1,11 @@
-use image::GenericImageView;
+use image::{GenericImageView, Rgba, RgbaImage};
+
+use crate::data::encoding::CoordinateEncoding;
+use crate::nn::model::Model;
+
+// Rows fed to the network at once while rendering, bounds the memory used
+// by large output grids.
+const RENDER_BATCH_SIZE: usize = 4096;
 
 pub fn load_image(
     path: &String,
@@ -26,3 +33,360 @@ pub fn load_image(
         }
     }
 }
+
+// Which pixel values become the targets of a sample.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Channels {
+    // 1 when the red channel is not zero, 0 otherwise (what `load_image` does).
+    Binary,
+    // Luma of the RGB channels.
+    Grayscale,
+    Rgb,
+    Rgba,
+    // Any subset of the RGBA channels by index (0 = red ... 3 = alpha).
+    Select(Vec<usize>),
+}
+
+impl Channels {
+    pub fn len(&self) -> usize {
+        match self {
+            Channels::Binary | Channels::Grayscale => 1,
+            Channels::Rgb => 3,
+            Channels::Rgba => 4,
+            Channels::Select(channels) => channels.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // RGBA indices of the target columns, empty for derived intensities.
+    fn rgba(&self) -> Vec<usize> {
+        match self {
+            Channels::Binary | Channels::Grayscale => vec![],
+            Channels::Rgb => vec![0, 1, 2],
+            Channels::Rgba => vec![0, 1, 2, 3],
+            Channels::Select(channels) => channels.clone(),
+        }
+    }
+}
+
+// Range the 0..255 channel values are mapped to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Normalization {
+    // [0, 1], matches sigmoid outputs.
+    Unit,
+    // [-1, 1], matches tanh and sine outputs.
+    Symmetric,
+}
+
+impl Normalization {
+    pub fn normalize(&self, value: u8) -> f64 {
+        let unit = value as f64 / 255.0;
+        match self {
+            Normalization::Unit => unit,
+            Normalization::Symmetric => unit * 2.0 - 1.0,
+        }
+    }
+
+    // Back to [0, 1], clamped since network outputs can leave the range.
+    pub fn denormalize(&self, value: f64) -> f64 {
+        let unit = match self {
+            Normalization::Unit => value,
+            Normalization::Symmetric => (value + 1.0) / 2.0,
+        };
+        unit.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageOptions {
+    pub channels: Channels,
+    pub normalization: Normalization,
+    pub encoding: CoordinateEncoding,
+}
+
+impl Default for ImageOptions {
+    fn default() -> ImageOptions {
+        ImageOptions {
+            channels: Channels::Binary,
+            normalization: Normalization::Unit,
+            encoding: CoordinateEncoding::Raw,
+        }
+    }
+}
+
+// An image as a regression dataset: one sample per pixel, row by row. The
+// inputs are the `(x / width, y / height)` coordinates after the encoding.
+pub struct ImageData {
+    pub coordinates: Vec<Vec<f64>>,
+    pub inputs: Vec<Vec<f64>>,
+    pub targets: Vec<Vec<f64>>,
+    pub width: u32,
+    pub height: u32,
+    pub options: ImageOptions,
+}
+
+// Fails on an unreadable image or an invalid channel selection.
+pub fn load_image_with(path: &str, options: ImageOptions) -> Result<ImageData, String> {
+    if let Channels::Select(channels) = &options.channels {
+        if channels.is_empty() || channels.iter().any(|&channel| channel > 3) {
+            return Err(format!(
+                "Invalid channel selection {:?}, channels are 0 to 3",
+                channels
+            ));
+        }
+    }
+
+    let img = image::open(path).map_err(|error| format!("Unable to open {}: {}", path, error))?;
+    let (width, height) = img.dimensions();
+
+    let coordinates = grid(width, height);
+    let targets = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| pixel_target(img.get_pixel(x, y), &options))
+        .collect();
+
+    Ok(ImageData {
+        inputs: options.encoding.encode(&coordinates),
+        coordinates,
+        targets,
+        width,
+        height,
+        options,
+    })
+}
+
+// `(x / width, y / height)` of every pixel, row by row. Grids of any size
+// cover the same [0, 1) range, so a network trained on one image can be
+// sampled at a higher resolution.
+pub fn grid(width: u32, height: u32) -> Vec<Vec<f64>> {
+    (0..height)
+        .flat_map(|y| {
+            (0..width).map(move |x| vec![x as f64 / width as f64, y as f64 / height as f64])
+        })
+        .collect()
+}
+
+fn pixel_target(pixel: Rgba<u8>, options: &ImageOptions) -> Vec<f64> {
+    let normalization = options.normalization;
+    match &options.channels {
+        Channels::Binary => {
+            let on = if pixel[0] > 0 { 255 } else { 0 };
+            vec![normalization.normalize(on)]
+        }
+        Channels::Grayscale => {
+            // Rec. 709 luma, as the image crate converts to grayscale.
+            let luma =
+                0.2126 * pixel[0] as f64 + 0.7152 * pixel[1] as f64 + 0.0722 * pixel[2] as f64;
+            vec![normalization.normalize(luma.round() as u8)]
+        }
+        channels => channels
+            .rgba()
+            .iter()
+            .map(|&channel| normalization.normalize(pixel[channel]))
+            .collect(),
+    }
+}
+
+impl ImageData {
+    pub fn input_size(&self) -> usize {
+        self.options.encoding.output_size(2)
+    }
+
+    pub fn target_size(&self) -> usize {
+        self.options.channels.len()
+    }
+
+    // Pixel coordinates of a sample.
+    pub fn pixel(&self, index: usize) -> (u32, u32) {
+        (index as u32 % self.width, index as u32 / self.width)
+    }
+
+    // RGBA color in [0, 1] of a target or network output row, binary and
+    // grayscale intensities are drawn as gray and missing channels as 0
+    // (alpha as 1).
+    pub fn color(&self, target: &[f64]) -> [f32; 4] {
+        let normalization = self.options.normalization;
+        if matches!(
+            self.options.channels,
+            Channels::Binary | Channels::Grayscale
+        ) {
+            let value = normalization.denormalize(target[0]) as f32;
+            return [value, value, value, 1.0];
+        }
+
+        let mut color = [0.0, 0.0, 0.0, 1.0];
+        for (&channel, &value) in self.options.channels.rgba().iter().zip(target.iter()) {
+            color[channel] = normalization.denormalize(value) as f32;
+        }
+        color
+    }
+
+    // Evaluates the network on a `width` x `height` grid in eval mode and
+    // converts the outputs to pixels. The previous mode is restored.
+    pub fn render<M: Model + ?Sized>(&self, model: &mut M, width: u32, height: u32) -> RgbaImage {
+        let inputs = self.options.encoding.encode(&grid(width, height));
+
+        let training = model.is_training();
+        model.eval_mode();
+        let outputs: Vec<Vec<f64>> = inputs
+            .chunks(RENDER_BATCH_SIZE)
+            .flat_map(|batch| model.feed_forward(batch.to_vec()))
+            .collect();
+        if training {
+            model.train_mode();
+        }
+
+        let mut image = RgbaImage::new(width, height);
+        for (pixel, output) in image.pixels_mut().zip(outputs.iter()) {
+            let color = self.color(output);
+            *pixel = Rgba(color.map(|channel| (channel * 255.0).round() as u8));
+        }
+        image
+    }
+
+    // Renders the network at `scale` times the training resolution.
+    pub fn upscale<M: Model + ?Sized>(&self, model: &mut M, scale: u32) -> RgbaImage {
+        self.render(model, self.width * scale, self.height * scale)
+    }
+}
+
+pub fn save_png(image: &RgbaImage, path: &str) -> image::ImageResult<()> {
+    image.save_with_format(path, image::ImageFormat::Png)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use crate::data::encoding::CoordinateEncoding;
+    use crate::image_nn::{
+        grid, load_image_with, pixel_target, save_png, Channels, ImageData, ImageOptions,
+        Normalization,
+    };
+    use crate::nn::activations::IDENTITY;
+    use crate::nn::network::Network;
+
+    fn options(channels: Channels, normalization: Normalization) -> ImageOptions {
+        ImageOptions {
+            channels,
+            normalization,
+            encoding: CoordinateEncoding::Raw,
+        }
+    }
+
+    #[test]
+    fn pixel_targets() {
+        let pixel = Rgba([255, 0, 51, 128]);
+        let unit = Normalization::Unit;
+
+        assert_eq!(
+            pixel_target(pixel, &options(Channels::Binary, unit)),
+            vec![1.0]
+        );
+        assert_eq!(
+            pixel_target(pixel, &options(Channels::Rgb, unit)),
+            vec![1.0, 0.0, 0.2]
+        );
+        assert_eq!(
+            pixel_target(
+                pixel,
+                &options(Channels::Select(vec![2]), Normalization::Symmetric)
+            ),
+            vec![-0.6]
+        );
+        let gray = pixel_target(pixel, &options(Channels::Grayscale, unit))[0];
+        assert!((gray - 58.0 / 255.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reports_load_errors() {
+        let message = load_image_with("missing.png", ImageOptions::default())
+            .err()
+            .unwrap();
+        assert!(
+            message.starts_with("Unable to open missing.png"),
+            "{}",
+            message
+        );
+
+        let message = load_image_with(
+            "assets/mnist_2.png",
+            options(Channels::Select(vec![4]), Normalization::Unit),
+        )
+        .err()
+        .unwrap();
+        assert!(message.contains("channels are 0 to 3"), "{}", message);
+    }
+
+    #[test]
+    fn colors_match_targets() {
+        let data = ImageData {
+            coordinates: vec![],
+            inputs: vec![],
+            targets: vec![],
+            width: 3,
+            height: 2,
+            options: options(Channels::Select(vec![1, 3]), Normalization::Symmetric),
+        };
+
+        assert_eq!(data.target_size(), 2);
+        assert_eq!(data.pixel(4), (1, 1));
+        assert_eq!(data.color(&[1.0, -1.0]), [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(data.color(&[3.0, 0.0]), [0.0, 1.0, 0.0, 0.5]);
+
+        let red = ImageData {
+            options: options(Channels::Select(vec![0]), Normalization::Unit),
+            ..data
+        };
+        assert_eq!(red.color(&[0.5]), [0.5, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn renders_any_resolution() {
+        assert_eq!(
+            grid(2, 2),
+            vec![
+                vec![0.0, 0.0],
+                vec![0.5, 0.0],
+                vec![0.0, 0.5],
+                vec![0.5, 0.5]
+            ]
+        );
+
+        let data = ImageData {
+            coordinates: vec![],
+            inputs: vec![],
+            targets: vec![],
+            width: 2,
+            height: 3,
+            options: options(Channels::Grayscale, Normalization::Unit),
+        };
+        // Brightness equal to the x coordinate.
+        let mut network = Network::new(vec![(2, IDENTITY), (1, IDENTITY)], 0.0);
+        network.weights[0].data = vec![vec![1.0], vec![0.0]];
+        network.biases[0].data = vec![vec![0.0]];
+
+        let image = data.upscale(&mut network, 4);
+        assert_eq!(image.dimensions(), (8, 12));
+        assert_eq!(image.get_pixel(4, 7).0, [128, 128, 128, 255]);
+        assert_eq!(image.get_pixel(0, 11).0, [0, 0, 0, 255]);
+        assert!(network.is_training());
+        network.eval_mode();
+        data.render(&mut network, 1, 1);
+        assert!(!network.is_training());
+
+        let path = std::env::temp_dir().join(format!(
+            "rust_nn_renders_any_resolution_{}.png",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap();
+        save_png(&image, path).unwrap();
+        let saved = image::open(path);
+        std::fs::remove_file(path).unwrap();
+        let saved = saved.unwrap().to_rgba8();
+        assert_eq!(saved, image);
+    }
+}
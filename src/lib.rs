@@ -1,2 +1,32 @@
-
-
+pub mod data {
+    pub mod augmentation;
+    pub mod csv;
+    pub mod dataset;
+    pub mod encoding;
+    pub mod image_folder;
+    pub mod loader;
+    pub mod mnist;
+    pub mod preprocessing;
+    pub mod split;
+}
+pub mod nn {
+    pub mod activations;
+    pub mod callbacks;
+    pub mod dropout;
+    pub mod embedding;
+    pub mod graph;
+    pub mod layer;
+    pub mod loss;
+    pub mod matrix;
+    pub mod metrics;
+    pub mod model;
+    pub mod network;
+    pub mod normalization;
+    pub mod optimizer;
+    pub mod regularization;
+    pub mod schedulers;
+    pub mod siren;
+    pub mod stability;
+    pub mod trainer;
+}
+pub mod image_nn;
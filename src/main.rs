@@ -1,16 +1,28 @@
+use std::{path::Path, sync::Arc};
+
 use macroquad::prelude::*;
 
-pub mod nn {
-    pub mod activations;
-    pub mod matrix;
-    pub mod network;
-}
-mod image_nn;
+use rust_nn::data::{dataset::InMemoryDataset, loader::DataLoader};
+use rust_nn::image_nn::{self, ImageData};
+use rust_nn::nn::network::Network;
+use rust_nn::nn::siren::siren_initialize;
 
-use nn::activations::SIGMOID;
-use nn::network::Network;
+mod gym {
+    pub mod architecture;
+    pub mod cli;
+    pub mod controls;
+    pub mod editor;
+    pub mod headless;
+    pub mod ui;
+    pub mod visualization;
+}
 
-use crate::nn::activations::{Activation, IDENTITY, RELU};
+use gym::architecture::Architecture;
+use gym::cli::{self, Options};
+use gym::controls::{self, Action, Controls};
+use gym::editor::Editor;
+use gym::headless;
+use gym::visualization::Visualization;
 
 pub struct Vector2 {
     x: f32,
@@ -25,72 +37,165 @@ pub fn lerp(start: f64, end: f64, amt: f64) -> f64 {
     return (1.0 - amt) * start + amt * end;
 }
 
-#[macroquad::main("Neural network gym.")]
-async fn main() {
+const MIN_LEARNING_RATE: f64 = 0.000001;
+const MAX_LEARNING_RATE: f64 = 0.001;
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("[ERROR] {}", message);
+    eprintln!("{}", cli::USAGE);
+    std::process::exit(2);
+}
+
+// Samples of every batch, one per row.
+type Batches = Vec<Vec<Vec<f64>>>;
+
+fn batches(image: &ImageData, batch_size: usize) -> (Batches, Batches) {
+    let dataset = InMemoryDataset::new(image.inputs.clone(), image.targets.clone());
+    let batches: Vec<_> = DataLoader::new(Arc::new(dataset), batch_size)
+        .batches()
+        .collect();
+    let inputs_batches = batches
+        .iter()
+        .map(|(inputs, _)| inputs.data.clone())
+        .collect();
+    let targets_batches = batches
+        .into_iter()
+        .map(|(_, targets)| targets.data)
+        .collect();
+    (inputs_batches, targets_batches)
+}
+
+fn new_network(options: &Options, architecture: &Architecture) -> Network<'static> {
+    let mut network = architecture.network(options.learning_rate, options.omega_0);
+    if architecture.has_sine() {
+        // Sine layers only train from the SIREN initialization.
+        let seed = options.seed.unwrap_or_else(::rand::random);
+        siren_initialize(&mut network, options.omega_0, seed);
+    } else if let Some(seed) = options.seed {
+        network.initialize(seed);
+    }
+    network.set_optimizer(options.optimizer);
+    network.set_check_numerics(true);
+    network
+}
+
+fn main() {
     let args: Vec<String> = std::env::args().collect();
-    let image_path = args.get(1);
-    match image_path {
-        None => {
-            eprintln!("[ERROR] Image path argument not found.");
-            std::process::exit(1);
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    let options = cli::parse(&args).unwrap_or_else(|message| exit_with_usage(&message));
+
+    let image = image_nn::load_image_with(
+        &options.image,
+        image_nn::ImageOptions {
+            channels: options.channels.clone(),
+            normalization: options.normalization,
+            encoding: options.encoding.clone(),
+        },
+    )
+    .unwrap_or_else(|message| exit_with_usage(&message));
+    let architecture = options
+        .layers
+        .clone()
+        .unwrap_or_else(|| Architecture::default_for(image.input_size(), image.target_size()));
+    if architecture.inputs() != image.input_size() || architecture.outputs() != image.target_size()
+    {
+        exit_with_usage(&format!(
+            "--layers {} maps {} inputs to {} outputs, the image needs {} inputs and {} outputs",
+            architecture,
+            architecture.inputs(),
+            architecture.outputs(),
+            image.input_size(),
+            image.target_size()
+        ));
+    }
+
+    let mut network = new_network(&options, &architecture);
+    if let Some(model) = options.model.as_ref().filter(|model| Path::new(model).exists()) {
+        if let Err(message) = controls::check_save_file(model, &network) {
+            exit_with_usage(&message);
         }
-        Some(_x) => {}
+        network.load(model.clone());
+        println!("Loaded the network from {}", model);
     }
 
-    let mut inputs: Vec<Vec<f64>> = vec![];
-    let mut targets: Vec<Vec<f64>> = vec![];
-    let mut image_width: f32 = 0.0;
-    let mut image_height: f32 = 0.0;
-    image_nn::load_image(
-        image_path.unwrap(),
-        &mut inputs,
-        &mut targets,
-        &mut image_width,
-        &mut image_height,
+    if let Some(headless_options) = &options.headless {
+        let (inputs_batches, targets_batches) = batches(&image, options.batch_size);
+        std::process::exit(headless::run(
+            headless_options,
+            &image,
+            &mut network,
+            &inputs_batches,
+            &targets_batches,
+            options.learning_rate,
+        ));
+    }
+
+    macroquad::Window::new(
+        "Neural network gym.",
+        gym(options, image, architecture, network),
     );
+}
 
-    const BATCH_SIZE: usize = 20;
-    let mut learning_rate = 0.000001;
+async fn gym(
+    options: Options,
+    image: ImageData,
+    mut nn_architecture: Architecture,
+    mut network: Network<'static>,
+) {
+    let targets = &image.targets;
+    let image_width = image.width as f32;
+    let image_height = image.height as f32;
 
-    
+    let mut learning_rate = options.learning_rate;
+    let (inputs_batches, targets_batches) = batches(&image, options.batch_size);
 
-    let inputs_batches = inputs
-        .chunks(BATCH_SIZE)
-        .into_iter()
-        .map(|x| x.to_owned())
-        .collect();
-    let targets_batches = targets
-        .chunks(BATCH_SIZE)
-        .into_iter()
-        .map(|x| x.to_owned())
-        .collect();
-
-    let mut learning_rate_slider_value = 0.4;
+    let mut learning_rate_slider_value = ((learning_rate - MIN_LEARNING_RATE)
+        / (MAX_LEARNING_RATE - MIN_LEARNING_RATE))
+        .clamp(0.0, 1.0);
 
-    #[rustfmt::skip]
-    let nn_architecture: Vec<(usize, Activation)> = vec![
-        (2, IDENTITY), 
-        (15, RELU),  
-        (15, RELU), 
-        (1, SIGMOID)
-    ];
-    let mut network = Network::new(nn_architecture.clone(), learning_rate.clone());
     let mut errors: Vec<f64> = vec![];
+    let mut editor = Editor::new(&nn_architecture);
+    let mut visualization = Visualization::new();
+    let mut controls = Controls::new();
+    let mut take_screenshot = false;
+    // Loss of every epoch, `errors` only keeps the last one of each frame.
+    let mut history: Vec<f64> = vec![];
+    let model_file = options
+        .model
+        .clone()
+        .unwrap_or_else(|| "model.json".to_string());
     loop {
         if is_key_pressed(KeyCode::Space) {
-            network = Network::new(nn_architecture.clone(), learning_rate.clone());
+            network = new_network(&options, &nn_architecture);
             errors = vec![];
+            history = vec![];
+        }
+        if is_key_pressed(KeyCode::E) {
+            let upscaled = image.upscale(&mut network, 4);
+            match image_nn::save_png(&upscaled, "learned_4x.png") {
+                Ok(()) => println!("Saved the learned image at 4x resolution to learned_4x.png"),
+                Err(error) => eprintln!("[ERROR] Unable to write learned_4x.png: {}", error),
+            }
         }
-        let mut current_error = 0.0;
-        for _epoch in 0..7 {
-            current_error =
-                network.train_one_epoch(&inputs_batches, &targets_batches, learning_rate);
+        let epochs = controls.epochs(options.epochs_per_frame);
+        for _epoch in 0..epochs {
+            history.push(network.train_one_epoch(
+                &inputs_batches,
+                &targets_batches,
+                learning_rate,
+            ));
         }
+        let current_error = history.last().copied().unwrap_or(0.0);
 
         // println!("weights = {:?}", network.weights);
         // println!("biases = {:?}", network.biases);
 
-        errors.push(current_error);
+        if epochs > 0 {
+            errors.push(current_error);
+        }
 
         clear_background(Color::new(0.18, 0.18, 0.18, 1.0));
         draw_text("IT WORKS!", 20.0, 20.0, 30.0, DARKGRAY);
@@ -109,6 +214,16 @@ async fn main() {
             DARKGRAY,
         );
 
+        if let Some(non_finite) = network.non_finite() {
+            draw_text(
+                &format!("{}, press Space to reset", non_finite),
+                20.0,
+                110.0 + screen_height() * 3.0 / 4.0,
+                24.0,
+                RED,
+            );
+        }
+
         // Error slider
         let slider_width = screen_width() / 2.0 - 20.0;
         let slider_position = Vector2 { x: 20.0, y: 120.0 };
@@ -131,12 +246,17 @@ async fn main() {
         };
 
         if is_mouse_button_down(MouseButton::Left)
+            && (mouse_position.y - slider_position.y).abs() < 10.0
             && vector2_distance(&slider_circle_position, &mouse_position) > 6.0
         {
             learning_rate_slider_value =
                 ((mouse_position.x - slider_position.x) / slider_width).clamp(0.0, 1.0) as f64;
 
-            learning_rate = lerp(0.000001, 0.001, learning_rate_slider_value);
+            learning_rate = lerp(
+                MIN_LEARNING_RATE,
+                MAX_LEARNING_RATE,
+                learning_rate_slider_value,
+            );
         }
         draw_circle(slider_circle_position.x, slider_circle_position.y, 6.0, RED);
 
@@ -144,10 +264,10 @@ async fn main() {
         let plot_padding_top = screen_height() / 4.0;
         let plot_height = screen_height() / 2.0;
         let plot_width = screen_width() / 2.0 - 30.0;
-        let max_error = errors.clone().into_iter().reduce(f64::max).unwrap() as f32;
+        let max_error = errors.clone().into_iter().reduce(f64::max).unwrap_or(1.0) as f32;
         let error_offset = plot_width / errors.len() as f32;
         draw_rectangle(0.0, plot_padding_top, plot_width, plot_height, DARKGRAY);
-        for i in 0..errors.len() - 1 {
+        for i in 0..errors.len().saturating_sub(1) {
             let err = errors[i] as f32;
             let err_2 = errors[i + 1] as f32;
             draw_line(
@@ -160,7 +280,55 @@ async fn main() {
             );
         }
 
-        let image_scale = 8.0;
+        draw_text(
+            &format!("Layers: {} (Tab to edit)", nn_architecture),
+            20.0,
+            145.0,
+            20.0,
+            DARKGRAY,
+        );
+        visualization.update(&network, 0.0, plot_padding_top, plot_width, plot_height);
+        let edited = editor.update(&nn_architecture, 0.0, plot_padding_top, plot_width);
+        if let Some(architecture) = edited {
+            nn_architecture = architecture;
+            network = new_network(&options, &nn_architecture);
+            errors = vec![];
+            history = vec![];
+        }
+
+        let controls_top = plot_padding_top + plot_height + 10.0;
+        match controls.update(0.0, controls_top, plot_width) {
+            Some(Action::Save) => {
+                controls.status = match network.try_save(&model_file) {
+                    Ok(()) => format!("Saved the network to {}", model_file),
+                    Err(error) => format!("Unable to write {}: {}", model_file, error),
+                };
+            }
+            Some(Action::Load) => match controls::check_save_file(&model_file, &network) {
+                Ok(()) => {
+                    network.load(model_file.clone());
+                    // Moments of the replaced weights don't apply to the loaded ones.
+                    network.set_optimizer(options.optimizer);
+                    network.clear_non_finite();
+                    controls.status = format!("Loaded the network from {}", model_file);
+                }
+                Err(message) => controls.status = message,
+            },
+            Some(Action::Screenshot) => take_screenshot = true,
+            Some(Action::ExportLoss) => {
+                controls.status = match controls::export_loss(&history, controls::LOSS_FILE) {
+                    Ok(()) => format!(
+                        "Wrote the loss of {} epochs to {}",
+                        history.len(),
+                        controls::LOSS_FILE
+                    ),
+                    Err(error) => format!("Unable to write {}: {}", controls::LOSS_FILE, error),
+                }
+            }
+            None => {}
+        }
+
+        let image_scale = options.scale;
 
         // IMAGE ORIGINAL VIEW
         let left_images_padding = screen_width() - image_width * image_scale - 60.0;
@@ -173,15 +341,15 @@ async fn main() {
             DARKGRAY,
         );
         top_images_padding += 10.0;
-        for sample_index in 0..inputs.len() {
-            let position = inputs.get(sample_index).unwrap();
-            let brightness = targets.get(sample_index).unwrap()[0] as f32;
+        for (sample_index, target) in targets.iter().enumerate() {
+            let (x, y) = image.pixel(sample_index);
+            let [r, g, b, a] = image.color(target);
             draw_rectangle(
-                left_images_padding + position[0] as f32 * image_width * image_scale,
-                top_images_padding + position[1] as f32 * image_height * image_scale,
+                left_images_padding + x as f32 * image_scale,
+                top_images_padding + y as f32 * image_scale,
                 image_scale,
                 image_scale,
-                Color::new(brightness, brightness, brightness, 1.0),
+                Color::new(r, g, b, a),
             )
         }
 
@@ -195,24 +363,33 @@ async fn main() {
             DARKGRAY,
         );
         top_images_padding += 10.0;
-        for sample_batch_index in 0..inputs_batches.len() {
-            let input = inputs_batches.get(sample_batch_index);
-            let positions = input.unwrap();
-            let targets = network.feed_forward(positions.clone());
-
-            for i in 0..positions.len() {
-                let position = positions.get(i).unwrap();
-                let target = *targets.get(i).unwrap().get(0).unwrap() as f32;
+        let mut sample_index = 0;
+        for positions in inputs_batches.iter() {
+            let outputs = network.feed_forward(positions.clone());
+            visualization.record(&network);
+
+            for output in outputs.iter() {
+                let (x, y) = image.pixel(sample_index);
+                let [r, g, b, a] = image.color(output);
                 draw_rectangle(
-                    left_images_padding + position[0] as f32 * image_width * image_scale,
-                    top_images_padding + position[1] as f32 * image_height * image_scale,
+                    left_images_padding + x as f32 * image_scale,
+                    top_images_padding + y as f32 * image_scale,
                     image_scale,
                     image_scale,
-                    Color::new(target, target, target, 1.0),
-                )
+                    Color::new(r, g, b, a),
+                );
+                sample_index += 1;
             }
         }
 
+        if take_screenshot {
+            controls.status = match controls::screenshot(controls::SCREENSHOT_FILE) {
+                Ok(()) => format!("Saved a screenshot to {}", controls::SCREENSHOT_FILE),
+                Err(error) => format!("Unable to write {}: {}", controls::SCREENSHOT_FILE, error),
+            };
+            take_screenshot = false;
+        }
+
         next_frame().await
     }
 }
@@ -1,4 +1,7 @@
-use std::f64::consts::E;
+use super::matrix::Matrix;
+
+// Smallest value passed to ln by `safe_ln`, keeps log-losses finite.
+pub const LOG_EPSILON: f64 = 1e-12;
 
 #[derive(Clone)]
 pub struct Activation<'a> {
@@ -12,7 +15,7 @@ pub const IDENTITY: Activation = Activation {
 };
 
 pub const SIGMOID: Activation = Activation {
-	function: &|x| 1.0 / (1.0 + E.powf(-x)),
+	function: &|x| stable_sigmoid(x),
 	derivative: &|x| x * (1.0 - x),
 };
 
@@ -25,3 +28,78 @@ pub const RELU: Activation = Activation {
 	function: &|x| x.max(0.0),
 	derivative: &|x| if x > 0.0 { 1.0 } else { 0.0 },
 };
+
+// Never evaluates exp of a large positive number, so it can't overflow.
+pub fn stable_sigmoid(x: f64) -> f64 {
+	if x >= 0.0 {
+		1.0 / (1.0 + (-x).exp())
+	} else {
+		let e = x.exp();
+		e / (1.0 + e)
+	}
+}
+
+pub fn safe_ln(x: f64) -> f64 {
+	x.max(LOG_EPSILON).ln()
+}
+
+pub fn log_sum_exp(values: &[f64]) -> f64 {
+	let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+	if !max.is_finite() {
+		return max;
+	}
+	max + values.iter().map(|x| (x - max).exp()).sum::<f64>().ln()
+}
+
+// Row-wise softmax with the row maximum subtracted before exponentiation.
+pub fn softmax(matrix: &Matrix) -> Matrix {
+	Matrix::from(
+		matrix
+			.data
+			.iter()
+			.map(|row| {
+				let max = row.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+				let exps: Vec<f64> = row.iter().map(|x| (x - max).exp()).collect();
+				let sum: f64 = exps.iter().sum();
+				exps.iter().map(|x| x / sum).collect()
+			})
+			.collect(),
+	)
+}
+
+pub fn log_softmax(matrix: &Matrix) -> Matrix {
+	Matrix::from(
+		matrix
+			.data
+			.iter()
+			.map(|row| {
+				let normalizer = log_sum_exp(row);
+				row.iter().map(|x| x - normalizer).collect()
+			})
+			.collect(),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::nn::activations::{log_softmax, softmax, SIGMOID};
+	use crate::nn::matrix::Matrix;
+
+	#[test]
+	fn sigmoid_is_stable() {
+		assert_eq!((SIGMOID.function)(-1000.0), 0.0);
+		assert_eq!((SIGMOID.function)(1000.0), 1.0);
+		assert!(((SIGMOID.function)(0.0) - 0.5).abs() < 1e-12);
+	}
+
+	#[test]
+	fn softmax_handles_large_values() {
+		let result = softmax(&Matrix::from(vec![vec![1000.0, 1000.0], vec![-1000.0, 0.0]]));
+		assert_eq!(result.data[0], vec![0.5, 0.5]);
+		assert!(result.data[1][0] >= 0.0 && result.data[1][0] < 1e-300);
+		assert_eq!(result.data[1][1], 1.0);
+
+		let result = log_softmax(&Matrix::from(vec![vec![1000.0, 0.0]]));
+		assert_eq!(result.data[0], vec![0.0, -1000.0]);
+	}
+}
@@ -0,0 +1,305 @@
+use super::{matrix::Matrix, model::Model, trainer::EpochLog};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CallbackAction {
+    Continue,
+    Stop,
+}
+
+// Hooks invoked by `Trainer::fit`. Returning `CallbackAction::Stop` from an
+// end hook finishes training after the current batch / epoch.
+pub trait Callback {
+    fn on_train_begin(&mut self, _model: &mut dyn Model) {}
+
+    fn on_epoch_begin(&mut self, _epoch: usize, _model: &mut dyn Model) {}
+
+    fn on_batch_begin(&mut self, _batch: usize) {}
+
+    fn on_batch_end(&mut self, _batch: usize, _loss: f64) -> CallbackAction {
+        CallbackAction::Continue
+    }
+
+    fn on_epoch_end(&mut self, _log: &EpochLog, _model: &mut dyn Model) -> CallbackAction {
+        CallbackAction::Continue
+    }
+
+    fn on_train_end(&mut self, _model: &mut dyn Model) {}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Min,
+    Max,
+}
+
+impl Mode {
+    pub fn improved(&self, value: f64, best: Option<f64>, min_delta: f64) -> bool {
+        match best {
+            None => !value.is_nan(),
+            Some(best) => match self {
+                Mode::Min => value < best - min_delta,
+                Mode::Max => value > best + min_delta,
+            },
+        }
+    }
+}
+
+// Every epoch log has the same values, so a misconfigured monitor fails on
+// the first epoch.
+fn monitored(log: &EpochLog, monitor: &str) -> f64 {
+    log.value(monitor).unwrap_or_else(|| {
+        let hint = if monitor.starts_with("val_") {
+            ", `val_` values need a validation set (see `Trainer::validation`)"
+        } else {
+            ", metrics need to be added with `Trainer::metric`"
+        };
+        panic!(
+            "Monitored value `{}` is not in the epoch log{}",
+            monitor, hint
+        )
+    })
+}
+
+pub struct EarlyStopping {
+    pub monitor: String,
+    pub patience: usize,
+    pub min_delta: f64,
+    pub mode: Mode,
+    pub restore_best_weights: bool,
+    pub best: Option<f64>,
+    pub best_epoch: Option<usize>,
+    pub stopped_epoch: Option<usize>,
+    wait: usize,
+    best_parameters: Option<Vec<Matrix>>,
+}
+
+impl EarlyStopping {
+    pub fn new(monitor: &str, patience: usize) -> EarlyStopping {
+        EarlyStopping {
+            monitor: monitor.to_string(),
+            patience,
+            min_delta: 0.0,
+            mode: Mode::Min,
+            restore_best_weights: false,
+            best: None,
+            best_epoch: None,
+            stopped_epoch: None,
+            wait: 0,
+            best_parameters: None,
+        }
+    }
+
+    pub fn mode(mut self, mode: Mode) -> EarlyStopping {
+        self.mode = mode;
+        self
+    }
+
+    pub fn min_delta(mut self, min_delta: f64) -> EarlyStopping {
+        self.min_delta = min_delta;
+        self
+    }
+
+    // Restores the parameters of the best epoch when training ends.
+    pub fn restore_best_weights(mut self) -> EarlyStopping {
+        self.restore_best_weights = true;
+        self
+    }
+}
+
+impl Callback for EarlyStopping {
+    fn on_train_begin(&mut self, _model: &mut dyn Model) {
+        self.best = None;
+        self.best_epoch = None;
+        self.stopped_epoch = None;
+        self.wait = 0;
+        self.best_parameters = None;
+    }
+
+    fn on_epoch_end(&mut self, log: &EpochLog, model: &mut dyn Model) -> CallbackAction {
+        let value = monitored(log, &self.monitor);
+
+        if self.mode.improved(value, self.best, self.min_delta) {
+            self.best = Some(value);
+            self.best_epoch = Some(log.epoch);
+            self.wait = 0;
+            if self.restore_best_weights {
+                self.best_parameters = Some(model.parameters());
+            }
+            return CallbackAction::Continue;
+        }
+
+        self.wait += 1;
+        if self.wait > self.patience {
+            self.stopped_epoch = Some(log.epoch);
+            return CallbackAction::Stop;
+        }
+        CallbackAction::Continue
+    }
+
+    fn on_train_end(&mut self, model: &mut dyn Model) {
+        if let Some(parameters) = self.best_parameters.take() {
+            model.set_parameters(parameters);
+        }
+    }
+}
+
+// Saves the model with `Model::save`. `{epoch}` in the path is replaced by
+// the epoch number. With `best_only` the model is saved whenever the
+// monitored value improves, otherwise every `period` epochs.
+pub struct ModelCheckpoint {
+    pub path: String,
+    pub monitor: String,
+    pub mode: Mode,
+    pub best_only: bool,
+    pub period: usize,
+    pub best: Option<f64>,
+}
+
+impl ModelCheckpoint {
+    pub fn every(path: &str, period: usize) -> ModelCheckpoint {
+        if period == 0 {
+            panic!("Checkpoint period must be greater than zero");
+        }
+
+        ModelCheckpoint {
+            path: path.to_string(),
+            monitor: "loss".to_string(),
+            mode: Mode::Min,
+            best_only: false,
+            period,
+            best: None,
+        }
+    }
+
+    pub fn best(path: &str, monitor: &str, mode: Mode) -> ModelCheckpoint {
+        ModelCheckpoint {
+            path: path.to_string(),
+            monitor: monitor.to_string(),
+            mode,
+            best_only: true,
+            period: 1,
+            best: None,
+        }
+    }
+
+    fn path_for(&self, epoch: usize) -> String {
+        self.path.replace("{epoch}", &epoch.to_string())
+    }
+}
+
+impl Callback for ModelCheckpoint {
+    fn on_train_begin(&mut self, _model: &mut dyn Model) {
+        self.best = None;
+    }
+
+    fn on_epoch_end(&mut self, log: &EpochLog, model: &mut dyn Model) -> CallbackAction {
+        if self.best_only {
+            let value = monitored(log, &self.monitor);
+            if self.mode.improved(value, self.best, 0.0) {
+                self.best = Some(value);
+                model.save(self.path_for(log.epoch));
+            }
+        } else if log.epoch % self.period == 0 {
+            model.save(self.path_for(log.epoch));
+        }
+        CallbackAction::Continue
+    }
+}
+
+// Stops training as soon as a batch loss is NaN or infinite.
+#[derive(Default)]
+pub struct TerminateOnNaN {
+    pub terminated_at_batch: Option<usize>,
+}
+
+impl TerminateOnNaN {
+    pub fn new() -> TerminateOnNaN {
+        TerminateOnNaN::default()
+    }
+}
+
+impl Callback for TerminateOnNaN {
+    fn on_batch_end(&mut self, batch: usize, loss: f64) -> CallbackAction {
+        if loss.is_finite() {
+            return CallbackAction::Continue;
+        }
+        self.terminated_at_batch = Some(batch);
+        CallbackAction::Stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::activations::IDENTITY;
+    use crate::nn::callbacks::{EarlyStopping, Mode, ModelCheckpoint, TerminateOnNaN};
+    use crate::nn::network::Network;
+    use crate::nn::trainer::Trainer;
+
+    fn dataset() -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+        let inputs: Vec<Vec<f64>> = (0..10).map(|i| vec![i as f64]).collect();
+        let targets = inputs.iter().map(|x| vec![3.0 * x[0]]).collect();
+        (inputs, targets)
+    }
+
+    #[test]
+    fn early_stopping_waits_for_patience() {
+        let (inputs, targets) = dataset();
+        let mut network = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.0);
+
+        let history = Trainer::new(100, 5)
+            .validation(inputs.clone(), targets.clone())
+            .callback(EarlyStopping::new("val_loss", 3))
+            .fit(&mut network, &inputs, &targets);
+
+        assert_eq!(history.epochs.len(), 5);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "`val_loss` is not in the epoch log, `val_` values need a validation set"
+    )]
+    fn missing_monitor_fails_on_first_epoch() {
+        let (inputs, targets) = dataset();
+        let mut network = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.0);
+
+        Trainer::new(100, 5)
+            .callback(EarlyStopping::new("val_loss", 3))
+            .fit(&mut network, &inputs, &targets);
+    }
+
+    #[test]
+    fn terminate_on_nan_stops_diverging_training() {
+        let (inputs, targets) = dataset();
+        let mut network = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 10.0);
+
+        let history = Trainer::new(1000, 1).callback(TerminateOnNaN::new()).fit(
+            &mut network,
+            &inputs,
+            &targets,
+        );
+
+        assert!(history.epochs.len() < 1000);
+        assert!(!history.epochs.last().unwrap().train_loss.is_finite());
+    }
+
+    #[test]
+    fn checkpoint_saves_best_model() {
+        let (inputs, targets) = dataset();
+        let mut network = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.001);
+        let path = std::env::temp_dir().join(format!(
+            "rust_nn_checkpoint_{}_{{epoch}}.json",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap();
+
+        Trainer::new(3, 5)
+            .callback(ModelCheckpoint::best(path, "loss", Mode::Min))
+            .fit(&mut network, &inputs, &targets);
+
+        let saved = path.replace("{epoch}", "1");
+        assert!(std::path::Path::new(&saved).exists());
+        for epoch in 1..=3 {
+            let _ = std::fs::remove_file(path.replace("{epoch}", &epoch.to_string()));
+        }
+    }
+}
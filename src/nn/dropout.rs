@@ -0,0 +1,242 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    layer::Layer,
+    matrix::{standard_normal, Matrix},
+};
+
+// -scale * alpha of the SELU activation.
+const ALPHA_PRIME: f64 = -1.758_099_340_847_376_6;
+
+fn check_rate(rate: f64) {
+    if !(0.0..1.0).contains(&rate) {
+        panic!("Dropout rate must be in [0, 1), got {}", rate);
+    }
+}
+
+fn bernoulli_mask(rng: &mut StdRng, rows: usize, cols: usize, keep: f64) -> Matrix {
+    let mut mask = Matrix::zeros(rows, cols);
+    for row in mask.data.iter_mut() {
+        for value in row.iter_mut() {
+            *value = if rng.gen::<f64>() < keep { 1.0 } else { 0.0 };
+        }
+    }
+    mask
+}
+
+// Inverted dropout: kept units are scaled by 1 / (1 - rate) while training,
+// so inference is a plain identity.
+pub struct Dropout {
+    pub rate: f64,
+    rng: StdRng,
+    mask: Option<Matrix>,
+}
+
+impl Dropout {
+    pub fn new(rate: f64, seed: u64) -> Dropout {
+        check_rate(rate);
+        Dropout {
+            rate,
+            rng: StdRng::seed_from_u64(seed),
+            mask: None,
+        }
+    }
+}
+
+impl Layer for Dropout {
+    fn forward(&mut self, input: &Matrix, training: bool) -> Matrix {
+        if !training || self.rate == 0.0 {
+            self.mask = None;
+            return input.clone();
+        }
+
+        let keep = 1.0 - self.rate;
+        let mask = bernoulli_mask(&mut self.rng, input.rows, input.cols, keep).map(&|x| x / keep);
+        let output = input.scalar_multiplication(&mask);
+        self.mask = Some(mask);
+        output
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> Matrix {
+        match &self.mask {
+            Some(mask) => gradient.scalar_multiplication(mask),
+            None => gradient.clone(),
+        }
+    }
+}
+
+// Dropout for self-normalizing networks: dropped units are set to the SELU
+// saturation value and the result is rescaled to keep mean and variance.
+pub struct AlphaDropout {
+    pub rate: f64,
+    rng: StdRng,
+    mask: Option<Matrix>,
+    a: f64,
+}
+
+impl AlphaDropout {
+    pub fn new(rate: f64, seed: u64) -> AlphaDropout {
+        check_rate(rate);
+        let keep = 1.0 - rate;
+        AlphaDropout {
+            rate,
+            rng: StdRng::seed_from_u64(seed),
+            mask: None,
+            a: (keep + ALPHA_PRIME.powi(2) * keep * rate).powf(-0.5),
+        }
+    }
+}
+
+impl Layer for AlphaDropout {
+    fn forward(&mut self, input: &Matrix, training: bool) -> Matrix {
+        if !training || self.rate == 0.0 {
+            self.mask = None;
+            return input.clone();
+        }
+
+        let keep = 1.0 - self.rate;
+        let a = self.a;
+        let b = -a * self.rate * ALPHA_PRIME;
+        let mask = bernoulli_mask(&mut self.rng, input.rows, input.cols, keep);
+
+        let mut output = Matrix::zeros(input.rows, input.cols);
+        for i in 0..input.rows {
+            for j in 0..input.cols {
+                let kept = mask.data[i][j];
+                output.data[i][j] = a * (input.data[i][j] * kept + ALPHA_PRIME * (1.0 - kept)) + b;
+            }
+        }
+
+        self.mask = Some(mask);
+        output
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> Matrix {
+        match &self.mask {
+            Some(mask) => gradient.scalar_multiplication(&mask.map(&|x| x * self.a)),
+            None => gradient.clone(),
+        }
+    }
+}
+
+// Additive zero-centered gaussian noise, only applied while training.
+pub struct GaussianNoise {
+    pub stddev: f64,
+    rng: StdRng,
+}
+
+impl GaussianNoise {
+    pub fn new(stddev: f64, seed: u64) -> GaussianNoise {
+        GaussianNoise {
+            stddev,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Layer for GaussianNoise {
+    fn forward(&mut self, input: &Matrix, training: bool) -> Matrix {
+        if !training || self.stddev == 0.0 {
+            return input.clone();
+        }
+
+        let mut output = input.clone();
+        for row in output.data.iter_mut() {
+            for value in row.iter_mut() {
+                *value += self.stddev * standard_normal(&mut self.rng);
+            }
+        }
+        output
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> Matrix {
+        gradient.clone()
+    }
+}
+
+// Drops individual connections of a weight matrix instead of units.
+// Attached to a network transform with `Network::set_drop_connect`.
+pub struct DropConnect {
+    pub rate: f64,
+    rng: StdRng,
+    mask: Option<Matrix>,
+}
+
+impl DropConnect {
+    pub fn new(rate: f64, seed: u64) -> DropConnect {
+        check_rate(rate);
+        DropConnect {
+            rate,
+            rng: StdRng::seed_from_u64(seed),
+            mask: None,
+        }
+    }
+
+    pub fn mask_weights(&mut self, weights: &Matrix, training: bool) -> Matrix {
+        if !training || self.rate == 0.0 {
+            self.mask = None;
+            return weights.clone();
+        }
+
+        let keep = 1.0 - self.rate;
+        let mask =
+            bernoulli_mask(&mut self.rng, weights.rows, weights.cols, keep).map(&|x| x / keep);
+        let masked = weights.scalar_multiplication(&mask);
+        self.mask = Some(mask);
+        masked
+    }
+
+    pub fn apply_mask(&self, gradient: &Matrix) -> Matrix {
+        match &self.mask {
+            Some(mask) => gradient.scalar_multiplication(mask),
+            None => gradient.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::dropout::{AlphaDropout, DropConnect, Dropout, GaussianNoise};
+    use crate::nn::layer::Layer;
+    use crate::nn::matrix::Matrix;
+
+    #[test]
+    fn dropout_is_identity_in_eval_mode() {
+        let input = Matrix::from(vec![vec![1.0, 2.0, 3.0]]);
+        let mut dropout = Dropout::new(0.5, 1);
+        let mut noise = GaussianNoise::new(1.0, 1);
+        let mut alpha = AlphaDropout::new(0.5, 1);
+
+        assert_eq!(dropout.forward(&input, false), input);
+        assert_eq!(noise.forward(&input, false), input);
+        assert_eq!(alpha.forward(&input, false), input);
+    }
+
+    #[test]
+    fn dropout_scales_kept_units_and_masks_gradient() {
+        let input = Matrix::from(vec![vec![1.0; 100]]);
+        let mut dropout = Dropout::new(0.5, 7);
+
+        let output = dropout.forward(&input, true);
+        assert!(output.data[0].iter().all(|&x| x == 0.0 || x == 2.0));
+        assert!(output.data[0].contains(&0.0));
+
+        let gradient = dropout.backward(&input);
+        assert_eq!(gradient, output);
+    }
+
+    #[test]
+    fn masks_are_reproducible_with_seed() {
+        let input = Matrix::from(vec![vec![1.0; 50]]);
+        let mut first = Dropout::new(0.3, 42);
+        let mut second = Dropout::new(0.3, 42);
+        assert_eq!(first.forward(&input, true), second.forward(&input, true));
+
+        let mut first = DropConnect::new(0.3, 42);
+        let mut second = DropConnect::new(0.3, 42);
+        assert_eq!(
+            first.mask_weights(&input, true),
+            second.mask_weights(&input, true)
+        );
+    }
+}
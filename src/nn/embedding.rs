@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use super::matrix::Matrix;
+
+pub struct Embedding {
+    pub weights: Matrix,
+    pub padding_idx: Option<usize>,
+    pub max_norm: Option<f64>,
+    ids: Vec<Vec<usize>>,
+}
+
+impl Embedding {
+    pub fn new(
+        num_embeddings: usize,
+        embedding_dim: usize,
+        padding_idx: Option<usize>,
+        max_norm: Option<f64>,
+    ) -> Embedding {
+        let mut weights = Matrix::random(num_embeddings, embedding_dim, -1.0, 1.0);
+
+        if let Some(index) = padding_idx {
+            if index >= num_embeddings {
+                panic!(
+                    "Padding index {} is out of range for {} embeddings",
+                    index, num_embeddings
+                );
+            }
+            weights.data[index] = vec![0.0; embedding_dim];
+        }
+
+        Embedding {
+            weights,
+            padding_idx,
+            max_norm,
+            ids: vec![],
+        }
+    }
+
+    pub fn embedding_dim(&self) -> usize {
+        self.weights.cols
+    }
+
+    // Every row of ids is one sample; the vectors of its ids are concatenated,
+    // so a sequence of n tokens becomes n * embedding_dim inputs for the network.
+    pub fn feed_forward(&mut self, ids: Vec<Vec<usize>>) -> Vec<Vec<f64>> {
+        let mut outputs = vec![];
+
+        for row in ids.iter() {
+            let mut output = Vec::with_capacity(row.len() * self.embedding_dim());
+            for &id in row.iter() {
+                if id >= self.weights.rows {
+                    panic!(
+                        "Embedding index {} is out of range for {} embeddings",
+                        id, self.weights.rows
+                    );
+                }
+                if let Some(max_norm) = self.max_norm {
+                    self.renormalize(id, max_norm);
+                }
+                output.extend_from_slice(&self.weights.data[id]);
+            }
+            outputs.push(output);
+        }
+
+        self.ids = ids;
+        outputs
+    }
+
+    // Only the rows looked up in the last feed_forward are touched; the padding
+    // row never receives a gradient.
+    pub fn back_propagate(&mut self, gradient: Vec<Vec<f64>>, learning_rate: f64) {
+        if gradient.len() != self.ids.len() {
+            panic!(
+                "Invalid gradient length. Expected {} rows, got {}",
+                self.ids.len(),
+                gradient.len()
+            );
+        }
+
+        let dim = self.embedding_dim();
+        let mut sparse_gradient: HashMap<usize, Vec<f64>> = HashMap::new();
+
+        for (row, ids) in gradient.iter().zip(self.ids.iter()) {
+            if row.len() != ids.len() * dim {
+                panic!(
+                    "Invalid gradient width. Expected {}, got {}",
+                    ids.len() * dim,
+                    row.len()
+                );
+            }
+            for (position, &id) in ids.iter().enumerate() {
+                if Some(id) == self.padding_idx {
+                    continue;
+                }
+                let accumulated = sparse_gradient.entry(id).or_insert_with(|| vec![0.0; dim]);
+                let slice = &row[position * dim..(position + 1) * dim];
+                for (value, gradient) in accumulated.iter_mut().zip(slice) {
+                    *value += gradient;
+                }
+            }
+        }
+
+        for (id, de_dw) in sparse_gradient {
+            for (weight, gradient) in self.weights.data[id].iter_mut().zip(de_dw) {
+                *weight -= learning_rate * gradient;
+            }
+        }
+    }
+
+    fn renormalize(&mut self, id: usize, max_norm: f64) {
+        let norm = self.weights.data[id]
+            .iter()
+            .map(|x| x * x)
+            .sum::<f64>()
+            .sqrt();
+
+        if norm > max_norm {
+            let scale = max_norm / (norm + 1e-7);
+            for value in self.weights.data[id].iter_mut() {
+                *value *= scale;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::embedding::Embedding;
+    use crate::nn::matrix::Matrix;
+
+    #[test]
+    fn feed_forward_concatenates_vectors() {
+        let mut embedding = Embedding::new(3, 2, None, None);
+        embedding.weights = Matrix::from(vec![vec![0.0, 1.0], vec![2.0, 3.0], vec![4.0, 5.0]]);
+
+        let result = embedding.feed_forward(vec![vec![2, 0], vec![1, 1]]);
+        assert_eq!(
+            result,
+            vec![vec![4.0, 5.0, 0.0, 1.0], vec![2.0, 3.0, 2.0, 3.0]]
+        );
+    }
+
+    #[test]
+    fn back_propagate_updates_only_looked_up_rows() {
+        let mut embedding = Embedding::new(4, 2, Some(0), None);
+        embedding.weights = Matrix::from(vec![
+            vec![0.0, 0.0],
+            vec![1.0, 1.0],
+            vec![2.0, 2.0],
+            vec![3.0, 3.0],
+        ]);
+
+        embedding.feed_forward(vec![vec![1, 0, 1]]);
+        embedding.back_propagate(vec![vec![1.0, 2.0, 5.0, 5.0, 1.0, 0.0]], 0.5);
+
+        assert_eq!(
+            embedding.weights,
+            Matrix::from(vec![
+                vec![0.0, 0.0],
+                vec![0.0, 0.0],
+                vec![2.0, 2.0],
+                vec![3.0, 3.0],
+            ])
+        );
+    }
+
+    #[test]
+    fn max_norm_renormalizes_looked_up_rows() {
+        let mut embedding = Embedding::new(2, 2, None, Some(1.0));
+        embedding.weights = Matrix::from(vec![vec![3.0, 4.0], vec![0.3, 0.4]]);
+
+        let result = embedding.feed_forward(vec![vec![0, 1]]);
+        assert!((result[0][0] - 0.6).abs() < 1e-6);
+        assert!((result[0][1] - 0.8).abs() < 1e-6);
+        assert_eq!(result[0][2..], [0.3, 0.4]);
+    }
+}
@@ -0,0 +1,475 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json};
+
+use super::{activations::Activation, loss::Loss, matrix::Matrix, model::Model};
+
+pub type NodeId = usize;
+
+enum Node<'a> {
+    Input {
+        size: usize,
+    },
+    Dense {
+        input: NodeId,
+        weights: Matrix,
+        biases: Matrix,
+        activation: Activation<'a>,
+    },
+    Add(Vec<NodeId>),
+    Concat(Vec<NodeId>),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveData {
+    parameters: Vec<Vec<Vec<f64>>>,
+}
+
+struct Head {
+    node: NodeId,
+    loss: Loss,
+    weight: f64,
+}
+
+// A model whose layers form a directed acyclic graph. Nodes can only refer to
+// nodes created before them, so the creation order is a topological order.
+pub struct Graph<'a> {
+    pub learning_rate: f64,
+    nodes: Vec<Node<'a>>,
+    sizes: Vec<usize>,
+    inputs: Vec<NodeId>,
+    heads: Vec<Head>,
+    values: Vec<Matrix>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new(learning_rate: f64) -> Graph<'a> {
+        Graph {
+            learning_rate,
+            nodes: vec![],
+            sizes: vec![],
+            inputs: vec![],
+            heads: vec![],
+            values: vec![],
+        }
+    }
+
+    pub fn input(&mut self, size: usize) -> NodeId {
+        let id = self.push(Node::Input { size }, size);
+        self.inputs.push(id);
+        id
+    }
+
+    pub fn dense(&mut self, input: NodeId, size: usize, activation: Activation<'a>) -> NodeId {
+        self.check_node(input);
+        let node = Node::Dense {
+            input,
+            weights: Matrix::random(self.sizes[input], size, -1.0, 1.0),
+            biases: Matrix::random(1, size, -1.0, 1.0),
+            activation,
+        };
+        self.push(node, size)
+    }
+
+    pub fn add(&mut self, inputs: Vec<NodeId>) -> NodeId {
+        if inputs.is_empty() {
+            panic!("Add node needs at least one input");
+        }
+        for &input in inputs.iter() {
+            self.check_node(input);
+        }
+        let size = self.sizes[inputs[0]];
+        if inputs.iter().any(|&input| self.sizes[input] != size) {
+            panic!("Attempted to add nodes of different sizes");
+        }
+        self.push(Node::Add(inputs), size)
+    }
+
+    pub fn concat(&mut self, inputs: Vec<NodeId>) -> NodeId {
+        if inputs.is_empty() {
+            panic!("Concat node needs at least one input");
+        }
+        for &input in inputs.iter() {
+            self.check_node(input);
+        }
+        let size = inputs.iter().map(|&input| self.sizes[input]).sum();
+        self.push(Node::Concat(inputs), size)
+    }
+
+    // Marks a node as an output head. The total error is the weighted sum of
+    // the head losses.
+    pub fn output(&mut self, node: NodeId, loss: Loss, weight: f64) {
+        self.check_node(node);
+        self.heads.push(Head { node, loss, weight });
+    }
+
+    pub fn size(&self, node: NodeId) -> usize {
+        self.check_node(node);
+        self.sizes[node]
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.inputs.iter().map(|&input| self.sizes[input]).sum()
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.heads.iter().map(|head| self.sizes[head.node]).sum()
+    }
+
+    pub fn feed_forward_multi(&mut self, inputs: Vec<Matrix>) -> Vec<Matrix> {
+        if inputs.len() != self.inputs.len() {
+            panic!(
+                "Graph has {} inputs, got {}",
+                self.inputs.len(),
+                inputs.len()
+            );
+        }
+
+        let mut values: Vec<Matrix> = Vec::with_capacity(self.nodes.len());
+        let mut given = inputs.into_iter();
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            let value = match node {
+                Node::Input { size } => {
+                    let value = given.next().unwrap();
+                    if value.cols != *size {
+                        panic!(
+                            "Input node {} expects {} columns, got {}",
+                            id, size, value.cols
+                        );
+                    }
+                    value
+                }
+                Node::Dense {
+                    input,
+                    weights,
+                    biases,
+                    activation,
+                } => values[*input]
+                    .dot_product(weights)
+                    .add(biases)
+                    .map(activation.function),
+                Node::Add(inputs) => inputs[1..]
+                    .iter()
+                    .fold(values[inputs[0]].clone(), |acc, &input| {
+                        acc.add(&values[input])
+                    }),
+                Node::Concat(inputs) => inputs[1..]
+                    .iter()
+                    .fold(values[inputs[0]].clone(), |acc, &input| {
+                        acc.concat(&values[input])
+                    }),
+            };
+            values.push(value);
+        }
+
+        self.values = values;
+        self.heads
+            .iter()
+            .map(|head| self.values[head.node].clone())
+            .collect()
+    }
+
+    pub fn head_errors(&self, outputs: &[Matrix], targets: &[Matrix]) -> Vec<f64> {
+        self.heads
+            .iter()
+            .zip(outputs.iter().zip(targets.iter()))
+            .map(|(head, (output, target))| head.loss.value(output, target))
+            .collect()
+    }
+
+    // Back propagates the head targets of the last feed_forward_multi and
+    // returns the gradients w.r.t. every graph input.
+    pub fn back_propagate_multi(
+        &mut self,
+        targets: Vec<Matrix>,
+        learning_rate: f64,
+    ) -> Vec<Matrix> {
+        if targets.len() != self.heads.len() {
+            panic!(
+                "Graph has {} output heads, got {} targets",
+                self.heads.len(),
+                targets.len()
+            );
+        }
+
+        let mut gradients: Vec<Option<Matrix>> = vec![None; self.nodes.len()];
+        for (head, target) in self.heads.iter().zip(targets.iter()) {
+            let weight = head.weight;
+            let gradient = head
+                .loss
+                .gradient(&self.values[head.node], target)
+                .map(&|x| x * weight);
+            accumulate(&mut gradients, head.node, gradient);
+        }
+
+        for id in (0..self.nodes.len()).rev() {
+            let gradient = match gradients[id].take() {
+                Some(gradient) => gradient,
+                None => continue,
+            };
+
+            match &mut self.nodes[id] {
+                Node::Input { .. } => {
+                    gradients[id] = Some(gradient);
+                }
+                Node::Dense {
+                    input,
+                    weights,
+                    biases,
+                    activation,
+                } => {
+                    let de_dt =
+                        gradient.scalar_multiplication(&self.values[id].map(activation.derivative));
+                    let de_dw = self.values[*input].transpose().dot_product(&de_dt);
+                    let de_db = de_dt.sum_by_axis(0);
+                    let de_dx = de_dt.dot_product(&weights.transpose());
+
+                    *weights = weights.subtract(&de_dw.map(&|x| x * learning_rate));
+                    *biases = biases.subtract(&de_db.map(&|x| x * learning_rate));
+
+                    let input = *input;
+                    accumulate(&mut gradients, input, de_dx);
+                }
+                Node::Add(inputs) => {
+                    for &input in inputs.iter() {
+                        accumulate(&mut gradients, input, gradient.clone());
+                    }
+                }
+                Node::Concat(inputs) => {
+                    let mut from = 0;
+                    for &input in inputs.iter() {
+                        let to = from + self.sizes[input];
+                        accumulate(&mut gradients, input, gradient.columns(from, to));
+                        from = to;
+                    }
+                }
+            }
+        }
+
+        self.inputs
+            .iter()
+            .map(|&input| {
+                gradients[input]
+                    .take()
+                    .unwrap_or_else(|| Matrix::zeros(self.values[input].rows, self.sizes[input]))
+            })
+            .collect()
+    }
+
+    // Weights and biases of every dense node in creation order.
+    pub fn parameters(&self) -> Vec<Matrix> {
+        let mut parameters = vec![];
+        for node in self.nodes.iter() {
+            if let Node::Dense {
+                weights, biases, ..
+            } = node
+            {
+                parameters.push(weights.clone());
+                parameters.push(biases.clone());
+            }
+        }
+        parameters
+    }
+
+    pub fn set_parameters(&mut self, parameters: Vec<Matrix>) {
+        let mut parameters = parameters.into_iter();
+        for node in self.nodes.iter_mut() {
+            if let Node::Dense {
+                weights, biases, ..
+            } = node
+            {
+                *weights = parameters.next().expect("Missing weights in parameters");
+                *biases = parameters.next().expect("Missing biases in parameters");
+            }
+        }
+    }
+
+    pub fn save(&self, file: String) {
+        let mut file = File::create(file).expect("Unable to touch save file");
+
+        file.write_all(
+            json!({
+                "parameters": self.parameters().into_iter().map(|matrix| matrix.data).collect::<Vec<Vec<Vec<f64>>>>()
+            })
+            .to_string()
+            .as_bytes(),
+        )
+        .expect("Unable to write to save file");
+    }
+
+    pub fn load(&mut self, file: String) {
+        let mut file = File::open(file).expect("Unable to open save file");
+        let mut buffer = String::new();
+
+        file.read_to_string(&mut buffer)
+            .expect("Unable to read save file");
+
+        let save_data: SaveData = from_str(&buffer).expect("Unable to serialize save data");
+
+        self.set_parameters(save_data.parameters.into_iter().map(Matrix::from).collect());
+    }
+
+    fn push(&mut self, node: Node<'a>, size: usize) -> NodeId {
+        self.nodes.push(node);
+        self.sizes.push(size);
+        self.nodes.len() - 1
+    }
+
+    fn check_node(&self, node: NodeId) {
+        if node >= self.nodes.len() {
+            panic!("Unknown graph node {}", node);
+        }
+    }
+
+    fn split(&self, data: Vec<Vec<f64>>, nodes: Vec<NodeId>) -> Vec<Matrix> {
+        let matrix = Matrix::from(data);
+        let expected: usize = nodes.iter().map(|&node| self.sizes[node]).sum();
+        if matrix.cols != expected {
+            panic!("Expected {} columns, got {}", expected, matrix.cols);
+        }
+
+        let mut from = 0;
+        nodes
+            .iter()
+            .map(|&node| {
+                let to = from + self.sizes[node];
+                let part = matrix.columns(from, to);
+                from = to;
+                part
+            })
+            .collect()
+    }
+}
+
+fn accumulate(gradients: &mut [Option<Matrix>], node: NodeId, gradient: Matrix) {
+    gradients[node] = Some(match gradients[node].take() {
+        Some(current) => current.add(&gradient),
+        None => gradient,
+    });
+}
+
+fn join(parts: Vec<Matrix>) -> Vec<Vec<f64>> {
+    let mut parts = parts.into_iter();
+    let first = parts.next().expect("Graph has no nodes to join");
+    parts.fold(first, |acc, part| acc.concat(&part)).data
+}
+
+impl Model for Graph<'_> {
+    fn feed_forward(&mut self, inputs: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+        let inputs = self.split(inputs, self.inputs.clone());
+        join(self.feed_forward_multi(inputs))
+    }
+
+    fn calculate_error(&self, outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+        let nodes: Vec<NodeId> = self.heads.iter().map(|head| head.node).collect();
+        let outputs = self.split(outputs.to_vec(), nodes.clone());
+        let targets = self.split(targets.to_vec(), nodes);
+
+        self.head_errors(&outputs, &targets)
+            .iter()
+            .zip(self.heads.iter())
+            .map(|(error, head)| error * head.weight)
+            .sum()
+    }
+
+    fn back_propagate(
+        &mut self,
+        _outputs: Vec<Vec<f64>>,
+        targets: Vec<Vec<f64>>,
+        learning_rate: f64,
+    ) -> Vec<Vec<f64>> {
+        let nodes: Vec<NodeId> = self.heads.iter().map(|head| head.node).collect();
+        let targets = self.split(targets, nodes);
+        join(self.back_propagate_multi(targets, learning_rate))
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn parameters(&self) -> Vec<Matrix> {
+        Graph::parameters(self)
+    }
+
+    fn set_parameters(&mut self, parameters: Vec<Matrix>) {
+        Graph::set_parameters(self, parameters)
+    }
+
+    fn save(&self, file: String) {
+        Graph::save(self, file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::activations::{IDENTITY, RELU, SIGMOID};
+    use crate::nn::graph::Graph;
+    use crate::nn::loss::Loss;
+    use crate::nn::matrix::Matrix;
+    use crate::nn::model::Model;
+
+    #[test]
+    fn residual_and_concat_shapes() {
+        let mut graph = Graph::new(0.01);
+        let x = graph.input(3);
+        let y = graph.input(2);
+        let hidden = graph.dense(x, 3, RELU);
+        let residual = graph.add(vec![x, hidden]);
+        let merged = graph.concat(vec![residual, y]);
+        let first = graph.dense(merged, 1, SIGMOID);
+        let second = graph.dense(merged, 2, IDENTITY);
+        graph.output(first, Loss::BinaryCrossEntropy, 1.0);
+        graph.output(second, Loss::MeanSquaredError, 0.5);
+
+        assert_eq!(graph.size(merged), 5);
+        assert_eq!(graph.input_size(), 5);
+        assert_eq!(graph.output_size(), 3);
+
+        let outputs = graph.feed_forward_multi(vec![
+            Matrix::from(vec![vec![0.1, 0.2, 0.3]; 4]),
+            Matrix::from(vec![vec![1.0, 0.0]; 4]),
+        ]);
+        assert_eq!((outputs[0].rows, outputs[0].cols), (4, 1));
+        assert_eq!((outputs[1].rows, outputs[1].cols), (4, 2));
+
+        let gradients =
+            graph.back_propagate_multi(vec![Matrix::zeros(4, 1), Matrix::zeros(4, 2)], 0.01);
+        assert_eq!((gradients[0].rows, gradients[0].cols), (4, 3));
+        assert_eq!((gradients[1].rows, gradients[1].cols), (4, 2));
+    }
+
+    #[test]
+    fn trains_through_model_interface() {
+        let mut graph = Graph::new(0.05);
+        let x = graph.input(2);
+        let hidden = graph.dense(x, 2, IDENTITY);
+        let residual = graph.add(vec![x, hidden]);
+        let output = graph.dense(residual, 1, IDENTITY);
+        graph.output(output, Loss::MeanSquaredError, 1.0);
+
+        let inputs = vec![vec![
+            vec![0.0, 1.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![0.5, 0.5],
+        ]];
+        let targets = vec![vec![vec![1.0], vec![-1.0], vec![0.0], vec![0.0]]];
+
+        let before = graph.train_one_epoch(&inputs, &targets, 0.05);
+        let mut after = before;
+        for _ in 0..200 {
+            after = graph.train_one_epoch(&inputs, &targets, 0.05);
+        }
+        assert!(after < before);
+        assert!(after < 0.01);
+    }
+}
@@ -0,0 +1,33 @@
+use super::matrix::Matrix;
+
+// Persistent parameters and statistics of a layer, one matrix per entry.
+pub type LayerState = Vec<Vec<Vec<f64>>>;
+
+// A layer placed after the activation of one of the network transforms.
+// It must keep the shape of its input.
+pub trait Layer {
+    fn forward(&mut self, input: &Matrix, training: bool) -> Matrix;
+
+    // Receives the gradient w.r.t. the layer output and returns the gradient
+    // w.r.t. the layer input. Gradients of the layer's own parameters are kept
+    // for `gradients`.
+    fn backward(&mut self, gradient: &Matrix) -> Matrix;
+
+    // Trainable parameters, updated by the network like its weights (clipping,
+    // numerics checks and optimizer included), in the order of `gradients`.
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        vec![]
+    }
+
+    // Gradients of `parameters_mut` from the last `backward`.
+    fn gradients(&self) -> Vec<Matrix> {
+        vec![]
+    }
+
+    // Used by `Network::save` and `Network::load`, stateless layers keep the defaults.
+    fn state(&self) -> LayerState {
+        vec![]
+    }
+
+    fn load_state(&mut self, _state: LayerState) {}
+}
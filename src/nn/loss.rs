@@ -0,0 +1,51 @@
+use super::{
+    activations::{safe_ln, LOG_EPSILON as EPSILON},
+    matrix::Matrix,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Loss {
+    MeanSquaredError,
+    BinaryCrossEntropy,
+}
+
+impl Loss {
+    pub fn value(&self, outputs: &Matrix, targets: &Matrix) -> f64 {
+        match self {
+            Loss::MeanSquaredError => {
+                let errors = targets.subtract(outputs).square();
+                errors.collect_sum() / errors.count() as f64
+            }
+            Loss::BinaryCrossEntropy => {
+                let mut sum = 0.0;
+                for i in 0..outputs.rows {
+                    for j in 0..outputs.cols {
+                        let output = outputs.data[i][j].clamp(EPSILON, 1.0 - EPSILON);
+                        let target = targets.data[i][j];
+                        sum -= target * safe_ln(output) + (1.0 - target) * safe_ln(1.0 - output);
+                    }
+                }
+                sum / outputs.count() as f64
+            }
+        }
+    }
+
+    // Gradient w.r.t. the outputs, scaled the same way as `Network::back_propagate`
+    // (summed over the batch, no 2 / n factor for the squared error).
+    pub fn gradient(&self, outputs: &Matrix, targets: &Matrix) -> Matrix {
+        match self {
+            Loss::MeanSquaredError => outputs.subtract(targets),
+            Loss::BinaryCrossEntropy => {
+                let mut result = Matrix::zeros(outputs.rows, outputs.cols);
+                for i in 0..outputs.rows {
+                    for j in 0..outputs.cols {
+                        let output = outputs.data[i][j].clamp(EPSILON, 1.0 - EPSILON);
+                        result.data[i][j] =
+                            (output - targets.data[i][j]) / (output * (1.0 - output));
+                    }
+                }
+                result
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use rand::{thread_rng, Rng};
+use std::f64::consts::PI;
 use std::fmt::{Debug, Formatter, Result};
 
 fn random_from_to(from: f64, to: f64) -> f64 {
@@ -6,6 +7,13 @@ fn random_from_to(from: f64, to: f64) -> f64 {
     (rng.gen::<f64>() * (to - from)) + from
 }
 
+// Box-Muller transform, rand 0.8 has no normal distribution without rand_distr.
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
 #[derive(Clone)]
 pub struct Matrix {
     pub rows: usize,
@@ -215,6 +223,41 @@ impl Matrix {
         )
     }
 
+    pub fn columns(&self, from: usize, to: usize) -> Matrix {
+        if from > to || to > self.cols {
+            panic!(
+                "Attempted to take columns {}..{} of matrix with {} columns",
+                from, to, self.cols
+            );
+        }
+
+        Matrix {
+            rows: self.rows,
+            cols: to - from,
+            data: self.data.iter().map(|row| row[from..to].to_vec()).collect(),
+        }
+    }
+
+    pub fn concat(&self, other: &Matrix) -> Matrix {
+        if self.rows != other.rows {
+            panic!(
+                "Attempted to concatenate matrix of incorrect dimensions. {}x{} | {}x{}",
+                self.rows, self.cols, other.rows, other.cols
+            );
+        }
+
+        Matrix {
+            rows: self.rows,
+            cols: self.cols + other.cols,
+            data: self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(left, right)| [left.as_slice(), right.as_slice()].concat())
+                .collect(),
+        }
+    }
+
     pub fn transpose(&self) -> Matrix {
         let mut res = Matrix::zeros(self.cols, self.rows);
 
@@ -277,4 +320,16 @@ mod tests {
         let result = B.sum_by_axis(1);
         assert_eq!(result, Matrix::from(vec![vec![1.0, 5.0]]));
     }
+
+    #[test]
+    fn concat_and_columns() {
+        let a = Matrix::from(vec![vec![1.0], vec![2.0]]);
+        let b = Matrix::from(vec![vec![3.0, 4.0], vec![5.0, 6.0]]);
+        let result = a.concat(&b);
+        assert_eq!(
+            result,
+            Matrix::from(vec![vec![1.0, 3.0, 4.0], vec![2.0, 5.0, 6.0]])
+        );
+        assert_eq!(result.columns(1, 3), b);
+    }
 }
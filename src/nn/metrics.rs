@@ -0,0 +1,366 @@
+use super::activations::safe_ln;
+
+// Every metric takes `feed_forward` outputs and targets row by row. Rows with
+// a single column are binary problems (threshold 0.5) read as the scores
+// [1 - p, p] of the two classes, wider rows are one-hot / probability vectors
+// decoded with argmax. Parametrized metrics return closures so they can be
+// passed to `Trainer::metric` directly.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Average {
+    Micro,
+    Macro,
+    Weighted,
+}
+
+pub fn class_index(row: &[f64]) -> usize {
+    if row.len() == 1 {
+        return if row[0] >= 0.5 { 1 } else { 0 };
+    }
+
+    let mut best = 0;
+    for (i, value) in row.iter().enumerate() {
+        if *value > row[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+// Class scores of a row, a single column p stands for [1 - p, p].
+fn class_scores(row: &[f64]) -> Vec<f64> {
+    if row.len() == 1 {
+        vec![1.0 - row[0], row[0]]
+    } else {
+        row.to_vec()
+    }
+}
+
+fn classes(outputs: &[Vec<f64>]) -> usize {
+    outputs.first().map(|row| row.len().max(2)).unwrap_or(2)
+}
+
+fn check_shapes(outputs: &[Vec<f64>], targets: &[Vec<f64>]) {
+    if outputs.len() != targets.len() {
+        panic!(
+            "Metric got {} outputs and {} targets",
+            outputs.len(),
+            targets.len()
+        );
+    }
+    if outputs.is_empty() {
+        panic!("Metric got no samples");
+    }
+}
+
+pub fn accuracy(outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+    check_shapes(outputs, targets);
+    let correct = outputs
+        .iter()
+        .zip(targets.iter())
+        .filter(|(output, target)| class_index(output) == class_index(target))
+        .count();
+    correct as f64 / outputs.len() as f64
+}
+
+pub fn top_k_accuracy(k: usize) -> impl Fn(&[Vec<f64>], &[Vec<f64>]) -> f64 {
+    move |outputs, targets| {
+        check_shapes(outputs, targets);
+        let correct = outputs
+            .iter()
+            .zip(targets.iter())
+            .filter(|(output, target)| {
+                let scores = class_scores(output);
+                let score = scores[class_index(target)];
+                let better = scores.iter().filter(|&&value| value > score).count();
+                better < k
+            })
+            .count();
+        correct as f64 / outputs.len() as f64
+    }
+}
+
+// Rows are true classes, columns are predicted classes.
+pub fn confusion_matrix(outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    check_shapes(outputs, targets);
+    let classes = classes(outputs);
+    let mut matrix = vec![vec![0; classes]; classes];
+    for (output, target) in outputs.iter().zip(targets.iter()) {
+        matrix[class_index(target)][class_index(output)] += 1;
+    }
+    matrix
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+// Per class (true positives, predicted positives, actual positives).
+fn class_counts(matrix: &[Vec<usize>]) -> Vec<(usize, usize, usize)> {
+    (0..matrix.len())
+        .map(|class| {
+            let true_positives = matrix[class][class];
+            let predicted = matrix.iter().map(|row| row[class]).sum();
+            let actual = matrix[class].iter().sum();
+            (true_positives, predicted, actual)
+        })
+        .collect()
+}
+
+fn averaged(
+    outputs: &[Vec<f64>],
+    targets: &[Vec<f64>],
+    average: Average,
+    score: fn(usize, usize, usize) -> f64,
+) -> f64 {
+    // Binary problems are averaged over both classes like wider ones.
+    let matrix = confusion_matrix(outputs, targets);
+    let counts = class_counts(&matrix);
+
+    match average {
+        Average::Micro => {
+            let (tp, predicted, actual) = counts
+                .iter()
+                .fold((0, 0, 0), |acc, c| (acc.0 + c.0, acc.1 + c.1, acc.2 + c.2));
+            score(tp, predicted, actual)
+        }
+        Average::Macro => {
+            counts
+                .iter()
+                .map(|&(tp, predicted, actual)| score(tp, predicted, actual))
+                .sum::<f64>()
+                / counts.len() as f64
+        }
+        Average::Weighted => {
+            let total: usize = counts.iter().map(|c| c.2).sum();
+            counts
+                .iter()
+                .map(|&(tp, predicted, actual)| score(tp, predicted, actual) * ratio(actual, total))
+                .sum()
+        }
+    }
+}
+
+fn precision_score(tp: usize, predicted: usize, _actual: usize) -> f64 {
+    ratio(tp, predicted)
+}
+
+fn recall_score(tp: usize, _predicted: usize, actual: usize) -> f64 {
+    ratio(tp, actual)
+}
+
+fn f1(tp: usize, predicted: usize, actual: usize) -> f64 {
+    ratio(2 * tp, predicted + actual)
+}
+
+pub fn precision(average: Average) -> impl Fn(&[Vec<f64>], &[Vec<f64>]) -> f64 {
+    move |outputs, targets| averaged(outputs, targets, average, precision_score)
+}
+
+pub fn recall(average: Average) -> impl Fn(&[Vec<f64>], &[Vec<f64>]) -> f64 {
+    move |outputs, targets| averaged(outputs, targets, average, recall_score)
+}
+
+pub fn f1_score(average: Average) -> impl Fn(&[Vec<f64>], &[Vec<f64>]) -> f64 {
+    move |outputs, targets| averaged(outputs, targets, average, f1)
+}
+
+// Area under the ROC curve from the rank statistic, ties count as half.
+fn binary_roc_auc(scores: &[f64], labels: &[bool]) -> f64 {
+    let mut pairs: Vec<(f64, bool)> = scores.iter().cloned().zip(labels.iter().cloned()).collect();
+    pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let positives = labels.iter().filter(|&&label| label).count();
+    let negatives = labels.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return f64::NAN;
+    }
+
+    let mut rank_sum = 0.0;
+    let mut i = 0;
+    while i < pairs.len() {
+        let mut j = i;
+        while j + 1 < pairs.len() && pairs[j + 1].0 == pairs[i].0 {
+            j += 1;
+        }
+        let average_rank = (i + j) as f64 / 2.0 + 1.0;
+        rank_sum += average_rank * pairs[i..=j].iter().filter(|pair| pair.1).count() as f64;
+        i = j + 1;
+    }
+
+    (rank_sum - (positives * (positives + 1)) as f64 / 2.0) / (positives * negatives) as f64
+}
+
+// Binary outputs use the single score column, wider outputs are averaged one-vs-rest
+// over the classes that have both positive and negative samples.
+pub fn roc_auc(outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+    check_shapes(outputs, targets);
+
+    if outputs[0].len() == 1 {
+        let scores: Vec<f64> = outputs.iter().map(|row| row[0]).collect();
+        let labels: Vec<bool> = targets.iter().map(|row| row[0] >= 0.5).collect();
+        return binary_roc_auc(&scores, &labels);
+    }
+
+    let expected: Vec<usize> = targets.iter().map(|row| class_index(row)).collect();
+    let scores: Vec<f64> = (0..outputs[0].len())
+        .map(|class| {
+            let scores: Vec<f64> = outputs.iter().map(|row| row[class]).collect();
+            let labels: Vec<bool> = expected.iter().map(|&e| e == class).collect();
+            binary_roc_auc(&scores, &labels)
+        })
+        .filter(|score| !score.is_nan())
+        .collect();
+
+    if scores.is_empty() {
+        return f64::NAN;
+    }
+    scores.iter().sum::<f64>() / scores.len() as f64
+}
+
+// Binary cross-entropy for single column outputs, categorical cross-entropy
+// over probability rows otherwise.
+pub fn log_loss(outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+    check_shapes(outputs, targets);
+
+    let total: f64 = outputs
+        .iter()
+        .zip(targets.iter())
+        .map(|(output, target)| {
+            if output.len() == 1 {
+                -(target[0] * safe_ln(output[0]) + (1.0 - target[0]) * safe_ln(1.0 - output[0]))
+            } else {
+                -output
+                    .iter()
+                    .zip(target.iter())
+                    .map(|(p, t)| t * safe_ln(*p))
+                    .sum::<f64>()
+            }
+        })
+        .sum();
+    total / outputs.len() as f64
+}
+
+fn residuals(outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> Vec<f64> {
+    check_shapes(outputs, targets);
+    outputs
+        .iter()
+        .zip(targets.iter())
+        .flat_map(|(output, target)| output.iter().zip(target.iter()).map(|(o, t)| t - o))
+        .collect()
+}
+
+pub fn mean_absolute_error(outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+    let residuals = residuals(outputs, targets);
+    residuals.iter().map(|r| r.abs()).sum::<f64>() / residuals.len() as f64
+}
+
+pub fn root_mean_squared_error(outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+    let residuals = residuals(outputs, targets);
+    (residuals.iter().map(|r| r * r).sum::<f64>() / residuals.len() as f64).sqrt()
+}
+
+// Coefficient of determination over all output columns together.
+pub fn r2_score(outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+    let residuals = residuals(outputs, targets);
+    let values: Vec<f64> = targets.iter().flatten().cloned().collect();
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+
+    let residual_sum: f64 = residuals.iter().map(|r| r * r).sum();
+    let total_sum: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    if total_sum == 0.0 {
+        return if residual_sum == 0.0 { 1.0 } else { 0.0 };
+    }
+    1.0 - residual_sum / total_sum
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::metrics::{
+        accuracy, confusion_matrix, f1_score, log_loss, mean_absolute_error, precision, r2_score,
+        recall, roc_auc, root_mean_squared_error, top_k_accuracy, Average,
+    };
+
+    fn assert_close(left: f64, right: f64) {
+        assert!((left - right).abs() < 1e-9, "{} != {}", left, right);
+    }
+
+    fn multiclass() -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+        let outputs = vec![
+            vec![0.7, 0.2, 0.1],
+            vec![0.1, 0.6, 0.3],
+            vec![0.3, 0.4, 0.3],
+            vec![0.2, 0.3, 0.5],
+            vec![0.5, 0.1, 0.4],
+        ];
+        let targets = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![0.0, 0.0, 1.0],
+            vec![0.0, 0.0, 1.0],
+        ];
+        (outputs, targets)
+    }
+
+    #[test]
+    fn classification_metrics() {
+        let (outputs, targets) = multiclass();
+
+        assert_close(accuracy(&outputs, &targets), 0.6);
+        assert_close(top_k_accuracy(2)(&outputs, &targets), 1.0);
+        assert_eq!(
+            confusion_matrix(&outputs, &targets),
+            vec![vec![1, 0, 0], vec![0, 1, 0], vec![1, 1, 1]]
+        );
+
+        // Per class precision 1/2, 1/2, 1 and recall 1, 1, 1/3.
+        assert_close(precision(Average::Macro)(&outputs, &targets), 2.0 / 3.0);
+        assert_close(recall(Average::Macro)(&outputs, &targets), 7.0 / 9.0);
+        assert_close(precision(Average::Micro)(&outputs, &targets), 0.6);
+        assert_close(
+            recall(Average::Weighted)(&outputs, &targets),
+            0.2 + 0.2 + 0.6 / 3.0,
+        );
+        assert_close(
+            f1_score(Average::Macro)(&outputs, &targets),
+            (2.0 / 3.0 + 2.0 / 3.0 + 0.5) / 3.0,
+        );
+    }
+
+    #[test]
+    fn binary_metrics() {
+        let outputs = vec![vec![0.1], vec![0.4], vec![0.35], vec![0.8]];
+        let targets = vec![vec![0.0], vec![0.0], vec![1.0], vec![1.0]];
+
+        assert_close(roc_auc(&outputs, &targets), 0.75);
+        assert_close(accuracy(&outputs, &targets), 0.75);
+        assert_close(top_k_accuracy(1)(&outputs, &targets), 0.75);
+        assert_close(top_k_accuracy(2)(&outputs, &targets), 1.0);
+        // Per class precision 2/3, 1 and recall 1, 1/2.
+        assert_close(precision(Average::Macro)(&outputs, &targets), 5.0 / 6.0);
+        assert_close(recall(Average::Macro)(&outputs, &targets), 0.75);
+        assert_close(
+            f1_score(Average::Weighted)(&outputs, &targets),
+            (0.8 + 2.0 / 3.0) / 2.0,
+        );
+        assert_close(
+            log_loss(&outputs, &targets),
+            -(0.9f64.ln() + 0.6f64.ln() + 0.35f64.ln() + 0.8f64.ln()) / 4.0,
+        );
+    }
+
+    #[test]
+    fn regression_metrics() {
+        let outputs = vec![vec![2.5], vec![0.0], vec![2.0], vec![8.0]];
+        let targets = vec![vec![3.0], vec![-0.5], vec![2.0], vec![7.0]];
+
+        assert_close(mean_absolute_error(&outputs, &targets), 0.5);
+        assert_close(root_mean_squared_error(&outputs, &targets), 0.375f64.sqrt());
+        assert_close(r2_score(&outputs, &targets), 0.948_608_137_044_967_9);
+    }
+}
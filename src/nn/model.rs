@@ -0,0 +1,66 @@
+use super::matrix::Matrix;
+
+// Common training interface of `Network` and `Graph`. Inputs and targets are
+// batches of rows; multi-input / multi-output models concatenate the columns
+// of all their inputs / heads in declaration order.
+pub trait Model {
+    fn feed_forward(&mut self, inputs: Vec<Vec<f64>>) -> Vec<Vec<f64>>;
+
+    fn calculate_error(&self, outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64;
+
+    // Updates the parameters and returns the gradient w.r.t. the inputs.
+    fn back_propagate(
+        &mut self,
+        outputs: Vec<Vec<f64>>,
+        targets: Vec<Vec<f64>>,
+        learning_rate: f64,
+    ) -> Vec<Vec<f64>>;
+
+    fn learning_rate(&self) -> f64;
+
+    fn set_learning_rate(&mut self, learning_rate: f64);
+
+    fn parameters(&self) -> Vec<Matrix>;
+
+    fn set_parameters(&mut self, parameters: Vec<Matrix>);
+
+    fn save(&self, file: String);
+
+    // Penalty term added to the reported loss, see `Regularization`.
+    fn regularization_loss(&self) -> f64 {
+        0.0
+    }
+
+    fn train_mode(&mut self) {}
+
+    fn eval_mode(&mut self) {}
+
+    // Models without a separate eval behavior are always training.
+    fn is_training(&self) -> bool {
+        true
+    }
+
+    fn train_one_epoch(
+        &mut self,
+        inputs: &[Vec<Vec<f64>>],
+        targets: &[Vec<Vec<f64>>],
+        learning_rate: f64,
+    ) -> f64 {
+        let mut error: f64 = 0.0;
+        for i in 0..inputs.len() {
+            let outputs = self.feed_forward(inputs[i].clone());
+            error += self.calculate_error(&outputs, &targets[i]);
+            self.back_propagate(outputs, targets[i].clone(), learning_rate);
+        }
+        error / inputs.len() as f64
+    }
+
+    fn train(&mut self, inputs: &[Vec<Vec<f64>>], targets: &[Vec<Vec<f64>>], epochs: usize) {
+        for i in 1..=epochs {
+            let error = self.train_one_epoch(inputs, targets, self.learning_rate());
+            if epochs < 100 || i % (epochs / 20) == 0 {
+                println!("Loss: {:.7}, Epoch {} of {}", error, i, epochs);
+            }
+        }
+    }
+}
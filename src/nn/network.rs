@@ -3,10 +3,20 @@ use std::{
     io::{Read, Write},
 };
 
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use serde_json::{from_str, json};
 
-use super::{activations::Activation, matrix::Matrix};
+use super::{
+    activations::Activation,
+    dropout::DropConnect,
+    layer::{Layer, LayerState},
+    matrix::Matrix,
+    model::Model,
+    optimizer::{Optimizer, OptimizerState},
+    regularization::Regularization,
+    stability::{find_non_finite, GradientClipping, NonFinite, Stage},
+};
 
 pub struct Network<'a> {
     pub weights: Vec<Matrix>,
@@ -14,12 +24,23 @@ pub struct Network<'a> {
     pub data: Vec<Matrix>,
     pub learning_rate: f64,
     layers: Vec<(usize, Activation<'a>)>,
+    activations: Vec<Matrix>,
+    modules: Vec<Vec<Box<dyn Layer>>>,
+    drop_connect: Vec<Option<DropConnect>>,
+    regularization: Vec<Regularization>,
+    gradient_clipping: Option<GradientClipping>,
+    optimizer: OptimizerState,
+    check_numerics: bool,
+    non_finite: Option<NonFinite>,
+    training: bool,
 }
 
 #[derive(Serialize, Deserialize)]
 struct SaveData {
     weights: Vec<Vec<Vec<f64>>>,
     biases: Vec<Vec<Vec<f64>>>,
+    #[serde(default)]
+    layers: Vec<Vec<LayerState>>,
 }
 
 impl Network<'_> {
@@ -32,33 +53,132 @@ impl Network<'_> {
             biases.push(Matrix::random(1, layers[i + 1].0, -1.0, 1.0));
         }
 
+        let transforms = weights.len();
+
         Network {
             layers,
             weights,
             biases,
             data: vec![],
             learning_rate,
+            activations: vec![],
+            modules: (0..transforms).map(|_| vec![]).collect(),
+            drop_connect: (0..transforms).map(|_| None).collect(),
+            regularization: vec![Regularization::default(); transforms],
+            gradient_clipping: None,
+            optimizer: OptimizerState::new(Optimizer::default()),
+            check_numerics: false,
+            non_finite: None,
+            training: true,
+        }
+    }
+
+    // Draws the weights and biases again from U(-1, 1) like `new`, but from a
+    // seeded generator so the initialization is reproducible.
+    pub fn initialize(&mut self, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for matrix in self.weights.iter_mut().chain(self.biases.iter_mut()) {
+            for value in matrix.data.iter_mut().flatten() {
+                *value = rng.gen_range(-1.0..1.0);
+            }
         }
     }
 
+    // Appends a layer after the activation of transform `index`
+    // (transform `index` maps layers[index] to layers[index + 1]).
+    pub fn add_layer(&mut self, index: usize, layer: Box<dyn Layer>) {
+        if index >= self.modules.len() {
+            panic!(
+                "Invalid transform index {}, network has {} transforms",
+                index,
+                self.modules.len()
+            );
+        }
+        self.modules[index].push(layer);
+    }
+
+    pub fn set_drop_connect(&mut self, index: usize, drop_connect: DropConnect) {
+        if index >= self.drop_connect.len() {
+            panic!(
+                "Invalid transform index {}, network has {} transforms",
+                index,
+                self.drop_connect.len()
+            );
+        }
+        self.drop_connect[index] = Some(drop_connect);
+    }
+
+    pub fn set_regularization(&mut self, index: usize, regularization: Regularization) {
+        if index >= self.regularization.len() {
+            panic!(
+                "Invalid transform index {}, network has {} transforms",
+                index,
+                self.regularization.len()
+            );
+        }
+        self.regularization[index] = regularization;
+    }
+
+    pub fn regularization_loss(&self) -> f64 {
+        self.regularization
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(regularization, weights)| regularization.penalty(weights))
+            .sum()
+    }
+
+    pub fn set_gradient_clipping(&mut self, gradient_clipping: Option<GradientClipping>) {
+        self.gradient_clipping = gradient_clipping;
+    }
+
+    // Replaces the optimizer, discarding its accumulated moments.
+    pub fn set_optimizer(&mut self, optimizer: Optimizer) {
+        self.optimizer = OptimizerState::new(optimizer);
+    }
+
+    pub fn optimizer(&self) -> Optimizer {
+        self.optimizer.optimizer
+    }
+
+    // When enabled, activations, gradients and parameters are scanned for
+    // NaN / infinite values and the first occurrence is kept in `non_finite`.
+    pub fn set_check_numerics(&mut self, enabled: bool) {
+        self.check_numerics = enabled;
+    }
+
+    pub fn non_finite(&self) -> Option<NonFinite> {
+        self.non_finite
+    }
+
+    pub fn clear_non_finite(&mut self) {
+        self.non_finite = None;
+    }
+
+    pub fn train_mode(&mut self) {
+        self.training = true;
+    }
+
+    pub fn eval_mode(&mut self) {
+        self.training = false;
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+
     pub fn train(
         &mut self,
-        inputs: &Vec<Vec<Vec<f64>>>,
-        targets: &Vec<Vec<Vec<f64>>>,
+        inputs: &[Vec<Vec<f64>>],
+        targets: &[Vec<Vec<f64>>],
         epochs: usize,
     ) {
-        for i in 1..=epochs {
-            let error = self.train_one_epoch(&inputs, &targets, self.learning_rate);
-            if epochs < 100 || i % (epochs / 20) == 0 {
-                println!("Loss: {:.7}, Epoch {} of {}", error, i, epochs);
-            }
-        }
+        Model::train(self, inputs, targets, epochs)
     }
 
     pub fn train_one_epoch(
         &mut self,
-        inputs: &Vec<Vec<Vec<f64>>>,
-        targets: &Vec<Vec<Vec<f64>>>,
+        inputs: &[Vec<Vec<f64>>],
+        targets: &[Vec<Vec<f64>>],
         learning_rate: f64,
     ) -> f64 {
         let mut error: f64 = 0.0;
@@ -71,29 +191,45 @@ impl Network<'_> {
             error += self.calculate_error(&outputs, &current_target);
             self.back_propagate(outputs, current_target, learning_rate);
         }
-        error / inputs.len() as f64
+        error / inputs.len() as f64 + self.regularization_loss()
     }
 
     pub fn feed_forward(&mut self, inputs: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
         let mut current = Matrix::from(inputs.clone());
 
         self.data = vec![current.clone()];
+        self.activations = vec![];
 
         for i in 0..self.layers.len() - 1 {
+            let weights = match &mut self.drop_connect[i] {
+                Some(drop_connect) => drop_connect.mask_weights(&self.weights[i], self.training),
+                None => self.weights[i].clone(),
+            };
+
             current = current
-                .dot_product(&self.weights[i])
+                .dot_product(&weights)
                 .add(&self.biases[i])
                 .map(self.layers[i].1.function);
 
+            self.activations.push(current.clone());
+
+            for module in self.modules[i].iter_mut() {
+                current = module.forward(&current, self.training);
+            }
+
+            if self.check_numerics {
+                record_non_finite(&mut self.non_finite, &current, Stage::Activations, i);
+            }
+
             self.data.push(current.clone());
         }
 
         current.data.to_owned()
     }
 
-    pub fn calculate_error(&self, outputs: &Vec<Vec<f64>>, targets: &Vec<Vec<f64>>) -> f64 {
-        let parsed = Matrix::from(outputs.clone());
-        let errors = Matrix::from(targets.clone()).subtract(&parsed).square();
+    pub fn calculate_error(&self, outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+        let parsed = Matrix::from(outputs.to_vec());
+        let errors = Matrix::from(targets.to_vec()).subtract(&parsed).square();
 
         return errors.clone().collect_sum() / errors.count() as f64;
     }
@@ -103,7 +239,7 @@ impl Network<'_> {
         outputs: Vec<Vec<f64>>,
         targets: Vec<Vec<f64>>,
         learning_rate: f64,
-    ) {
+    ) -> Vec<Vec<f64>> {
         if targets[0].len() != self.layers[self.layers.len() - 1].0 {
             panic!("Invalid targets length");
         }
@@ -120,31 +256,162 @@ impl Network<'_> {
         */
 
         let targets_matrix = Matrix::from(targets);
-    
-        let mut de_dt = Matrix::from(outputs).subtract(&targets_matrix);
 
-        for i in (0..self.layers.len() - 1).rev() {
-            let de_dw = self.data[i].transpose().dot_product(&de_dt);
+        // Gradient of half the squared error w.r.t. the output of the current
+        // transform (after its layers). It goes through the derivative of each
+        // activation, evaluated at the cached activation output, the last one
+        // included. The returned input gradient uses the weights as they were
+        // before this update.
+        let mut gradient = Matrix::from(outputs).subtract(&targets_matrix);
+        let transforms = self.layers.len() - 1;
+        let mut weight_gradients = vec![Matrix::zeros(0, 0); transforms];
+        let mut bias_gradients = vec![Matrix::zeros(0, 0); transforms];
+
+        for i in (0..transforms).rev() {
+            for module in self.modules[i].iter_mut().rev() {
+                gradient = module.backward(&gradient);
+                if self.check_numerics {
+                    for layer_gradient in module.gradients() {
+                        record_non_finite(&mut self.non_finite, &layer_gradient, Stage::Gradients, i);
+                    }
+                }
+            }
+
+            let de_dt = gradient.scalar_multiplication(
+                &self.activations[i].map(self.layers[i].1.derivative),
+            );
+
+            let mut de_dw = self.data[i]
+                .transpose()
+                .dot_product(&de_dt)
+                .add(&self.regularization[i].gradient(&self.weights[i]));
             let de_db = de_dt.sum_by_axis(0);
 
-            self.weights[i] =
-                self.weights[i].subtract(&de_dw.map(&|x| x * learning_rate));
-            self.biases[i] = self.biases[i].subtract(&de_db.map(&|x| x * learning_rate));
+            let weights = match &self.drop_connect[i] {
+                Some(drop_connect) => {
+                    de_dw = drop_connect.apply_mask(&de_dw);
+                    drop_connect.apply_mask(&self.weights[i])
+                }
+                None => self.weights[i].clone(),
+            };
+            gradient = de_dt.dot_product(&weights.transpose());
+
+            // Checked as they are produced, so a non-finite gradient is named
+            // after the transform it started in rather than a lower one.
+            if self.check_numerics {
+                record_non_finite(&mut self.non_finite, &de_dw, Stage::Gradients, i);
+                record_non_finite(&mut self.non_finite, &de_db, Stage::Gradients, i);
+            }
+
+            weight_gradients[i] = de_dw;
+            bias_gradients[i] = de_db;
+        }
+
+        let mut layer_gradients: Vec<Matrix> = self
+            .modules
+            .iter()
+            .flatten()
+            .flat_map(|module| module.gradients())
+            .collect();
+
+        if let Some(clipping) = self.gradient_clipping {
+            let mut clipped =
+                clipping.clip([weight_gradients, bias_gradients, layer_gradients].concat());
+            layer_gradients = clipped.split_off(2 * transforms);
+            bias_gradients = clipped.split_off(transforms);
+            weight_gradients = clipped;
+        }
+
+        self.optimizer.next_step();
+        // Layer parameters come after the biases in the optimizer.
+        let mut layer_gradients = layer_gradients.into_iter();
+        let mut index = 2 * transforms;
+        for i in 0..transforms {
+            let regularization = self.regularization[i];
+            self.weights[i] = self.weights[i].subtract(&self.optimizer.update(
+                i,
+                &weight_gradients[i],
+                learning_rate,
+            ));
+            self.weights[i] = regularization.constrain(
+                &regularization.decay(&self.weights[i], learning_rate),
+            );
+            self.biases[i] = self.biases[i].subtract(&self.optimizer.update(
+                transforms + i,
+                &bias_gradients[i],
+                learning_rate,
+            ));
+
+            if self.check_numerics {
+                record_non_finite(&mut self.non_finite, &self.weights[i], Stage::Weights, i);
+                record_non_finite(&mut self.non_finite, &self.biases[i], Stage::Biases, i);
+            }
+
+            for module in self.modules[i].iter_mut() {
+                for parameter in module.parameters_mut() {
+                    let gradient = layer_gradients
+                        .next()
+                        .expect("Layer has fewer gradients than parameters");
+                    *parameter = parameter.subtract(&self.optimizer.update(
+                        index,
+                        &gradient,
+                        learning_rate,
+                    ));
+                    index += 1;
+                    if self.check_numerics {
+                        record_non_finite(&mut self.non_finite, parameter, Stage::Layers, i);
+                    }
+                }
+            }
+        }
+
+        gradient.data
+    }
 
-            let de_dh = (&de_dt).dot_product(&self.weights[i].transpose());
-            de_dt = de_dh.map(self.layers[i].1.derivative);
+    // Weights, biases and layer states in a flat list, used to snapshot and
+    // restore the network in memory (see `EarlyStopping`).
+    pub fn parameters(&self) -> Vec<Matrix> {
+        let mut parameters = self.weights.clone();
+        parameters.extend(self.biases.iter().cloned());
+        for module in self.modules.iter().flatten() {
+            parameters.extend(module.state().into_iter().map(Matrix::from));
+        }
+        parameters
+    }
+
+    pub fn set_parameters(&mut self, parameters: Vec<Matrix>) {
+        let transforms = self.weights.len();
+        let mut parameters = parameters.into_iter();
+
+        for i in 0..transforms {
+            self.weights[i] = parameters.next().expect("Missing weights in parameters");
+        }
+        for i in 0..transforms {
+            self.biases[i] = parameters.next().expect("Missing biases in parameters");
+        }
+        for module in self.modules.iter_mut().flatten() {
+            let size = module.state().len();
+            let state = parameters.by_ref().take(size).map(|matrix| matrix.data).collect();
+            module.load_state(state);
         }
     }
 
     pub fn save(&self, file: String) {
-        let mut file = File::create(file).expect("Unable to touch save file");
+        self.try_save(&file)
+            .unwrap_or_else(|error| panic!("Unable to write save file {}: {}", file, error));
+    }
+
+    // Like `save`, but returns the error instead of panicking.
+    pub fn try_save(&self, file: &str) -> std::io::Result<()> {
+        let mut file = File::create(file)?;
 
         file.write_all(
 			json!({
 				"weights": self.weights.clone().into_iter().map(|matrix| matrix.data).collect::<Vec<Vec<Vec<f64>>>>(),
-				"biases": self.biases.clone().into_iter().map(|matrix| matrix.data).collect::<Vec<Vec<Vec<f64>>>>()
+				"biases": self.biases.clone().into_iter().map(|matrix| matrix.data).collect::<Vec<Vec<Vec<f64>>>>(),
+				"layers": self.modules.iter().map(|modules| modules.iter().map(|module| module.state()).collect()).collect::<Vec<Vec<LayerState>>>()
 			}).to_string().as_bytes(),
-		).expect("Unable to write to save file");
+		)
     }
 
     pub fn load(&mut self, file: String) {
@@ -166,5 +433,281 @@ impl Network<'_> {
 
         self.weights = weights;
         self.biases = biases;
+
+        for (modules, states) in self.modules.iter_mut().zip(save_data.layers) {
+            if modules.len() != states.len() {
+                panic!(
+                    "Save file has {} layers for a transform with {} layers",
+                    states.len(),
+                    modules.len()
+                );
+            }
+            for (module, state) in modules.iter_mut().zip(states) {
+                module.load_state(state);
+            }
+        }
+    }
+}
+
+fn record_non_finite(
+    non_finite: &mut Option<NonFinite>,
+    matrix: &Matrix,
+    stage: Stage,
+    transform: usize,
+) {
+    if non_finite.is_none() {
+        *non_finite = find_non_finite(matrix, stage, transform);
+    }
+}
+
+impl Model for Network<'_> {
+    fn feed_forward(&mut self, inputs: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+        Network::feed_forward(self, inputs)
+    }
+
+    fn calculate_error(&self, outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+        Network::calculate_error(self, outputs, targets)
+    }
+
+    fn back_propagate(
+        &mut self,
+        outputs: Vec<Vec<f64>>,
+        targets: Vec<Vec<f64>>,
+        learning_rate: f64,
+    ) -> Vec<Vec<f64>> {
+        Network::back_propagate(self, outputs, targets, learning_rate)
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn parameters(&self) -> Vec<Matrix> {
+        Network::parameters(self)
+    }
+
+    fn set_parameters(&mut self, parameters: Vec<Matrix>) {
+        Network::set_parameters(self, parameters)
+    }
+
+    fn save(&self, file: String) {
+        Network::save(self, file)
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        Network::regularization_loss(self)
+    }
+
+    fn train_mode(&mut self) {
+        Network::train_mode(self)
+    }
+
+    fn eval_mode(&mut self) {
+        Network::eval_mode(self)
+    }
+
+    fn is_training(&self) -> bool {
+        Network::is_training(self)
+    }
+
+    fn train_one_epoch(
+        &mut self,
+        inputs: &[Vec<Vec<f64>>],
+        targets: &[Vec<Vec<f64>>],
+        learning_rate: f64,
+    ) -> f64 {
+        Network::train_one_epoch(self, inputs, targets, learning_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::activations::{IDENTITY, SIGMOID, TANH};
+    use crate::nn::dropout::Dropout;
+    use crate::nn::matrix::Matrix;
+    use crate::nn::network::Network;
+    use crate::nn::normalization::BatchNorm;
+    use crate::nn::stability::{GradientClipping, Stage};
+
+    #[test]
+    fn dropout_only_active_in_train_mode() {
+        let mut network = Network::new(vec![(3, IDENTITY), (50, IDENTITY), (1, IDENTITY)], 0.01);
+        network.add_layer(0, Box::new(Dropout::new(0.5, 3)));
+        let input = vec![vec![0.5, -0.2, 0.1]];
+
+        network.eval_mode();
+        let first = network.feed_forward(input.clone());
+        let second = network.feed_forward(input.clone());
+        assert_eq!(first, second);
+
+        network.train_mode();
+        let dropped = network.feed_forward(input.clone());
+        assert!(network.data[1].data[0].contains(&0.0));
+        assert_ne!(first, dropped);
+    }
+
+    // Half the squared error, the loss `back_propagate` differentiates.
+    fn half_squared_error(network: &mut Network, input: &[f64], target: &[f64]) -> f64 {
+        let outputs = network.feed_forward(vec![input.to_vec()]);
+        outputs[0]
+            .iter()
+            .zip(target)
+            .map(|(output, target)| (output - target).powi(2) / 2.0)
+            .sum()
+    }
+
+    #[test]
+    fn back_propagate_matches_finite_differences() {
+        let mut network = Network::new(vec![(2, TANH), (3, SIGMOID), (2, IDENTITY)], 0.1);
+        network.initialize(5);
+        let input = [0.3, -0.7];
+        let target = [0.2, 0.9];
+        let step = 1e-6;
+
+        let weights = network.weights.clone();
+        let mut expected_weights = vec![];
+        for (i, shape) in weights.iter().enumerate() {
+            let mut gradient = Matrix::zeros(shape.rows, shape.cols);
+            for row in 0..shape.rows {
+                for col in 0..shape.cols {
+                    network.weights[i].data[row][col] += step;
+                    let above = half_squared_error(&mut network, &input, &target);
+                    network.weights[i].data[row][col] -= 2.0 * step;
+                    let below = half_squared_error(&mut network, &input, &target);
+                    network.weights[i].data[row][col] += step;
+                    gradient.data[row][col] = (above - below) / (2.0 * step);
+                }
+            }
+            expected_weights.push(gradient);
+        }
+        let expected_input: Vec<f64> = (0..input.len())
+            .map(|j| {
+                let mut shifted = input;
+                shifted[j] += step;
+                let above = half_squared_error(&mut network, &shifted, &target);
+                shifted[j] -= 2.0 * step;
+                let below = half_squared_error(&mut network, &shifted, &target);
+                (above - below) / (2.0 * step)
+            })
+            .collect();
+
+        let learning_rate = 0.1;
+        let outputs = network.feed_forward(vec![input.to_vec()]);
+        let input_gradient = network.back_propagate(outputs, vec![target.to_vec()], learning_rate);
+
+        for j in 0..input.len() {
+            assert!((input_gradient[0][j] - expected_input[j]).abs() < 1e-6);
+        }
+        for (i, before) in weights.iter().enumerate() {
+            let gradient = before
+                .subtract(&network.weights[i])
+                .map(&|x| x / learning_rate);
+            for (row, expected) in gradient.data.iter().zip(&expected_weights[i].data) {
+                for (value, expected) in row.iter().zip(expected) {
+                    assert!((value - expected).abs() < 1e-6, "{} != {}", value, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn save_and_load_keep_layer_state() {
+        let architecture = vec![(2, IDENTITY), (4, IDENTITY), (1, IDENTITY)];
+        let mut network = Network::new(architecture.clone(), 0.01);
+        network.add_layer(0, Box::new(BatchNorm::new(4, 0.1)));
+        for _ in 0..5 {
+            network.train_one_epoch(
+                &[vec![vec![0.1, 0.9], vec![0.7, 0.2], vec![0.4, 0.4]]],
+                &[vec![vec![1.0], vec![0.0], vec![0.5]]],
+                0.1,
+            );
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "rust_nn_save_and_load_keep_layer_state_{}.json",
+            std::process::id()
+        ));
+        network.save(path.to_str().unwrap().to_string());
+
+        let mut loaded = Network::new(architecture, 0.01);
+        loaded.add_layer(0, Box::new(BatchNorm::new(4, 0.1)));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            loaded.load(path.to_str().unwrap().to_string())
+        }));
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        network.eval_mode();
+        loaded.eval_mode();
+        let input = vec![vec![0.3, 0.6]];
+        let expected = network.feed_forward(input.clone());
+        let result = loaded.feed_forward(input);
+        assert!((expected[0][0] - result[0][0]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn check_numerics_names_first_transform() {
+        let mut network = Network::new(vec![(1, IDENTITY), (2, IDENTITY), (1, IDENTITY)], 0.1);
+        network.set_check_numerics(true);
+        network.weights[1].data[0][0] = f64::INFINITY;
+
+        network.feed_forward(vec![vec![1.0]]);
+        let report = network.non_finite().unwrap();
+        assert_eq!(report.stage, Stage::Activations);
+        assert_eq!(report.transform, 1);
+    }
+
+    #[test]
+    fn check_numerics_names_transform_where_gradient_started() {
+        let mut network = Network::new(
+            vec![(1, IDENTITY), (2, IDENTITY), (2, IDENTITY), (1, IDENTITY)],
+            0.1,
+        );
+        network.set_check_numerics(true);
+
+        // The NaN target only enters the gradient of the last transform, and
+        // flows back into the lower ones from there.
+        let outputs = network.feed_forward(vec![vec![1.0]]);
+        network.back_propagate(outputs, vec![vec![f64::NAN]], 0.1);
+        let report = network.non_finite().unwrap();
+        assert_eq!(report.stage, Stage::Gradients);
+        assert_eq!(report.transform, 2);
+    }
+
+    #[test]
+    fn gradient_clipping_limits_update() {
+        let mut network = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 1.0);
+        network.weights[0] = Matrix::from(vec![vec![0.0]]);
+        network.biases[0] = Matrix::from(vec![vec![0.0]]);
+        network.set_gradient_clipping(Some(GradientClipping::Value(0.5)));
+
+        let outputs = network.feed_forward(vec![vec![10.0]]);
+        network.back_propagate(outputs, vec![vec![100.0]], 1.0);
+
+        assert_eq!(network.weights[0], Matrix::from(vec![vec![0.5]]));
+        assert_eq!(network.biases[0], Matrix::from(vec![vec![0.5]]));
+    }
+
+    #[test]
+    fn layer_parameters_go_through_clipping() {
+        let mut network = Network::new(vec![(2, IDENTITY), (3, IDENTITY), (1, IDENTITY)], 1.0);
+        network.add_layer(0, Box::new(BatchNorm::new(3, 0.1)));
+        network.set_gradient_clipping(Some(GradientClipping::Value(0.01)));
+
+        let outputs = network.feed_forward(vec![vec![0.1, 0.9], vec![0.7, 0.2]]);
+        network.back_propagate(outputs, vec![vec![100.0], vec![-100.0]], 1.0);
+
+        // Weights and biases of the 2 transforms come first, then gamma and beta.
+        let parameters = network.parameters();
+        for value in parameters[4].data[0].iter() {
+            assert!((value - 1.0).abs() <= 0.01 + 1e-12, "gamma {}", value);
+        }
+        for value in parameters[5].data[0].iter() {
+            assert!(value.abs() <= 0.01 + 1e-12, "beta {}", value);
+        }
     }
 }
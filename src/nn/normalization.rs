@@ -0,0 +1,356 @@
+use super::{
+    layer::{Layer, LayerState},
+    matrix::Matrix,
+};
+
+const EPSILON: f64 = 1e-5;
+
+fn load_row(state: &mut LayerState, name: &str, size: usize) -> Vec<f64> {
+    if state.is_empty() {
+        panic!("Missing {} in saved layer state", name);
+    }
+    let row = state.remove(0).remove(0);
+    if row.len() != size {
+        panic!(
+            "Invalid {} size in saved layer state. Expected {}, got {}",
+            name,
+            size,
+            row.len()
+        );
+    }
+    row
+}
+
+// Normalizes every feature over the batch. Running statistics collected
+// while training are used in eval mode.
+pub struct BatchNorm {
+    pub gamma: Matrix,
+    pub beta: Matrix,
+    pub running_mean: Matrix,
+    pub running_var: Matrix,
+    pub momentum: f64,
+    normalized: Matrix,
+    std_inv: Vec<f64>,
+    // Gradients of gamma and beta from the last `backward`.
+    gradients: Vec<Matrix>,
+    training: bool,
+}
+
+impl BatchNorm {
+    pub fn new(features: usize, momentum: f64) -> BatchNorm {
+        BatchNorm {
+            gamma: Matrix::from(vec![vec![1.0; features]]),
+            beta: Matrix::zeros(1, features),
+            running_mean: Matrix::zeros(1, features),
+            running_var: Matrix::from(vec![vec![1.0; features]]),
+            momentum,
+            normalized: Matrix::zeros(0, features),
+            std_inv: vec![],
+            gradients: vec![],
+            training: false,
+        }
+    }
+}
+
+impl Layer for BatchNorm {
+    fn forward(&mut self, input: &Matrix, training: bool) -> Matrix {
+        if input.cols != self.gamma.cols {
+            panic!(
+                "BatchNorm expects {} features, got {}",
+                self.gamma.cols, input.cols
+            );
+        }
+
+        let rows = input.rows as f64;
+        let mut normalized = Matrix::zeros(input.rows, input.cols);
+        self.std_inv = vec![0.0; input.cols];
+        self.training = training;
+
+        for j in 0..input.cols {
+            let (mean, var) = if training {
+                let mean = (0..input.rows).map(|i| input.data[i][j]).sum::<f64>() / rows;
+                let var = (0..input.rows)
+                    .map(|i| (input.data[i][j] - mean).powi(2))
+                    .sum::<f64>()
+                    / rows;
+
+                let unbiased = if input.rows > 1 {
+                    var * rows / (rows - 1.0)
+                } else {
+                    var
+                };
+                self.running_mean.data[0][j] =
+                    (1.0 - self.momentum) * self.running_mean.data[0][j] + self.momentum * mean;
+                self.running_var.data[0][j] =
+                    (1.0 - self.momentum) * self.running_var.data[0][j] + self.momentum * unbiased;
+
+                (mean, var)
+            } else {
+                (self.running_mean.data[0][j], self.running_var.data[0][j])
+            };
+
+            let std_inv = 1.0 / (var + EPSILON).sqrt();
+            self.std_inv[j] = std_inv;
+            for i in 0..input.rows {
+                normalized.data[i][j] = (input.data[i][j] - mean) * std_inv;
+            }
+        }
+
+        let output = normalized
+            .scalar_multiplication(&Matrix::from(vec![self.gamma.data[0].clone(); input.rows]))
+            .add(&self.beta);
+        self.normalized = normalized;
+        output
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> Matrix {
+        let rows = gradient.rows as f64;
+        let mut de_dx = Matrix::zeros(gradient.rows, gradient.cols);
+        let de_dgamma = gradient
+            .scalar_multiplication(&self.normalized)
+            .sum_by_axis(0);
+        let de_dbeta = gradient.sum_by_axis(0);
+
+        for j in 0..gradient.cols {
+            let gamma = self.gamma.data[0][j];
+            let std_inv = self.std_inv[j];
+
+            if !self.training {
+                for i in 0..gradient.rows {
+                    de_dx.data[i][j] = gradient.data[i][j] * gamma * std_inv;
+                }
+                continue;
+            }
+
+            let sum_de_dn: f64 = (0..gradient.rows)
+                .map(|i| gradient.data[i][j] * gamma)
+                .sum();
+            let sum_de_dn_n: f64 = (0..gradient.rows)
+                .map(|i| gradient.data[i][j] * gamma * self.normalized.data[i][j])
+                .sum();
+
+            for i in 0..gradient.rows {
+                let de_dn = gradient.data[i][j] * gamma;
+                de_dx.data[i][j] = std_inv / rows
+                    * (rows * de_dn - sum_de_dn - self.normalized.data[i][j] * sum_de_dn_n);
+            }
+        }
+
+        self.gradients = vec![de_dgamma, de_dbeta];
+
+        de_dx
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        self.gradients.clone()
+    }
+
+    fn state(&self) -> LayerState {
+        vec![
+            self.gamma.data.clone(),
+            self.beta.data.clone(),
+            self.running_mean.data.clone(),
+            self.running_var.data.clone(),
+        ]
+    }
+
+    fn load_state(&mut self, mut state: LayerState) {
+        let features = self.gamma.cols;
+        self.gamma = Matrix::from(vec![load_row(&mut state, "gamma", features)]);
+        self.beta = Matrix::from(vec![load_row(&mut state, "beta", features)]);
+        self.running_mean = Matrix::from(vec![load_row(&mut state, "running mean", features)]);
+        self.running_var = Matrix::from(vec![load_row(&mut state, "running variance", features)]);
+    }
+}
+
+// Normalizes every sample over its features, behaves the same in train and eval mode.
+pub struct LayerNorm {
+    pub gamma: Matrix,
+    pub beta: Matrix,
+    normalized: Matrix,
+    std_inv: Vec<f64>,
+    gradients: Vec<Matrix>,
+}
+
+impl LayerNorm {
+    pub fn new(features: usize) -> LayerNorm {
+        LayerNorm {
+            gamma: Matrix::from(vec![vec![1.0; features]]),
+            beta: Matrix::zeros(1, features),
+            normalized: Matrix::zeros(0, features),
+            std_inv: vec![],
+            gradients: vec![],
+        }
+    }
+}
+
+impl Layer for LayerNorm {
+    fn forward(&mut self, input: &Matrix, _training: bool) -> Matrix {
+        if input.cols != self.gamma.cols {
+            panic!(
+                "LayerNorm expects {} features, got {}",
+                self.gamma.cols, input.cols
+            );
+        }
+
+        let cols = input.cols as f64;
+        let mut normalized = Matrix::zeros(input.rows, input.cols);
+        self.std_inv = vec![0.0; input.rows];
+
+        for i in 0..input.rows {
+            let row = &input.data[i];
+            let mean = row.iter().sum::<f64>() / cols;
+            let var = row.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / cols;
+            let std_inv = 1.0 / (var + EPSILON).sqrt();
+
+            self.std_inv[i] = std_inv;
+            for (value, x) in normalized.data[i].iter_mut().zip(row.iter()) {
+                *value = (x - mean) * std_inv;
+            }
+        }
+
+        let output = normalized
+            .scalar_multiplication(&Matrix::from(vec![self.gamma.data[0].clone(); input.rows]))
+            .add(&self.beta);
+        self.normalized = normalized;
+        output
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> Matrix {
+        let cols = gradient.cols as f64;
+        let mut de_dx = Matrix::zeros(gradient.rows, gradient.cols);
+        let de_dgamma = gradient
+            .scalar_multiplication(&self.normalized)
+            .sum_by_axis(0);
+        let de_dbeta = gradient.sum_by_axis(0);
+
+        for i in 0..gradient.rows {
+            let de_dn: Vec<f64> = (0..gradient.cols)
+                .map(|j| gradient.data[i][j] * self.gamma.data[0][j])
+                .collect();
+            let sum_de_dn: f64 = de_dn.iter().sum();
+            let sum_de_dn_n: f64 = de_dn
+                .iter()
+                .zip(self.normalized.data[i].iter())
+                .map(|(g, n)| g * n)
+                .sum();
+
+            let normalized = &self.normalized.data[i];
+            for (j, value) in de_dx.data[i].iter_mut().enumerate() {
+                *value = self.std_inv[i] / cols
+                    * (cols * de_dn[j] - sum_de_dn - normalized[j] * sum_de_dn_n);
+            }
+        }
+
+        self.gradients = vec![de_dgamma, de_dbeta];
+
+        de_dx
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        self.gradients.clone()
+    }
+
+    fn state(&self) -> LayerState {
+        vec![self.gamma.data.clone(), self.beta.data.clone()]
+    }
+
+    fn load_state(&mut self, mut state: LayerState) {
+        let features = self.gamma.cols;
+        self.gamma = Matrix::from(vec![load_row(&mut state, "gamma", features)]);
+        self.beta = Matrix::from(vec![load_row(&mut state, "beta", features)]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::layer::Layer;
+    use crate::nn::matrix::Matrix;
+    use crate::nn::normalization::{BatchNorm, LayerNorm};
+
+    fn numeric_input_gradient(layer: &mut dyn Layer, input: &Matrix, weights: &Matrix) -> Matrix {
+        let step = 1e-6;
+        let mut result = Matrix::zeros(input.rows, input.cols);
+        for i in 0..input.rows {
+            for j in 0..input.cols {
+                let mut plus = input.clone();
+                plus.data[i][j] += step;
+                let mut minus = input.clone();
+                minus.data[i][j] -= step;
+                let loss_plus = layer
+                    .forward(&plus, true)
+                    .scalar_multiplication(weights)
+                    .collect_sum();
+                let loss_minus = layer
+                    .forward(&minus, true)
+                    .scalar_multiplication(weights)
+                    .collect_sum();
+                result.data[i][j] = (loss_plus - loss_minus) / (2.0 * step);
+            }
+        }
+        result
+    }
+
+    fn assert_close(left: &Matrix, right: &Matrix) {
+        for i in 0..left.rows {
+            for j in 0..left.cols {
+                assert!(
+                    (left.data[i][j] - right.data[i][j]).abs() < 1e-4,
+                    "{:?} != {:?}",
+                    left,
+                    right
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn batch_norm_normalizes_features_and_tracks_running_stats() {
+        let mut layer = BatchNorm::new(2, 1.0);
+        let input = Matrix::from(vec![vec![1.0, 10.0], vec![3.0, 30.0]]);
+
+        let output = layer.forward(&input, true);
+        assert_close(
+            &output,
+            &Matrix::from(vec![vec![-1.0, -1.0], vec![1.0, 1.0]]),
+        );
+        assert_eq!(layer.running_mean, Matrix::from(vec![vec![2.0, 20.0]]));
+        assert_eq!(layer.running_var, Matrix::from(vec![vec![2.0, 200.0]]));
+
+        let output = layer.forward(&Matrix::from(vec![vec![2.0, 20.0]]), false);
+        assert_close(&output, &Matrix::from(vec![vec![0.0, 0.0]]));
+    }
+
+    #[test]
+    fn backward_matches_numeric_gradient() {
+        let input = Matrix::from(vec![
+            vec![0.5, -1.0, 2.0],
+            vec![1.5, 0.3, -0.7],
+            vec![-0.2, 0.8, 0.1],
+        ]);
+        let weights = Matrix::from(vec![
+            vec![0.3, -0.5, 1.0],
+            vec![2.0, 0.1, -0.4],
+            vec![0.7, 0.9, -1.2],
+        ]);
+
+        let mut layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(BatchNorm::new(3, 0.1)),
+            Box::new(LayerNorm::new(3)),
+        ];
+        for layer in layers.iter_mut() {
+            let expected = numeric_input_gradient(layer.as_mut(), &input, &weights);
+            layer.forward(&input, true);
+            let result = layer.backward(&weights);
+            assert_close(&result, &expected);
+        }
+    }
+}
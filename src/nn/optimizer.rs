@@ -0,0 +1,162 @@
+use super::matrix::Matrix;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Optimizer {
+    #[default]
+    Sgd,
+    // Classical momentum: velocity = momentum * velocity + gradient.
+    Momentum {
+        momentum: f64,
+    },
+    Adam {
+        beta1: f64,
+        beta2: f64,
+        epsilon: f64,
+    },
+}
+
+impl Optimizer {
+    pub fn momentum() -> Optimizer {
+        Optimizer::Momentum { momentum: 0.9 }
+    }
+
+    pub fn adam() -> Optimizer {
+        Optimizer::Adam {
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+        }
+    }
+}
+
+// Moment estimates of every parameter, indexed the way the model passes them
+// to `update`. They start at zero the first time a parameter is seen.
+pub struct OptimizerState {
+    pub optimizer: Optimizer,
+    step: i32,
+    first: Vec<Matrix>,
+    second: Vec<Matrix>,
+}
+
+impl OptimizerState {
+    pub fn new(optimizer: Optimizer) -> OptimizerState {
+        OptimizerState {
+            optimizer,
+            step: 0,
+            first: vec![],
+            second: vec![],
+        }
+    }
+
+    // Starts the update of a batch, called once before its `update`s.
+    pub fn next_step(&mut self) {
+        self.step += 1;
+    }
+
+    fn moment<'a>(moments: &'a mut Vec<Matrix>, index: usize, like: &Matrix) -> &'a mut Matrix {
+        while moments.len() <= index {
+            moments.push(Matrix::zeros(0, 0));
+        }
+        if moments[index].rows != like.rows || moments[index].cols != like.cols {
+            moments[index] = Matrix::zeros(like.rows, like.cols);
+        }
+        &mut moments[index]
+    }
+
+    // The amount to subtract from parameter `index`.
+    pub fn update(&mut self, index: usize, gradient: &Matrix, learning_rate: f64) -> Matrix {
+        match self.optimizer {
+            Optimizer::Sgd => gradient.map(&|x| x * learning_rate),
+            Optimizer::Momentum { momentum } => {
+                let velocity = OptimizerState::moment(&mut self.first, index, gradient);
+                *velocity = velocity.map(&|x| x * momentum).add(gradient);
+                velocity.map(&|x| x * learning_rate)
+            }
+            Optimizer::Adam {
+                beta1,
+                beta2,
+                epsilon,
+            } => {
+                let first = OptimizerState::moment(&mut self.first, index, gradient);
+                *first = first
+                    .map(&|x| x * beta1)
+                    .add(&gradient.map(&|x| x * (1.0 - beta1)));
+                let first = first.clone();
+                let second = OptimizerState::moment(&mut self.second, index, gradient);
+                *second = second
+                    .map(&|x| x * beta2)
+                    .add(&gradient.square().map(&|x| x * (1.0 - beta2)));
+
+                let step = self.step.max(1);
+                let first_correction = 1.0 - beta1.powi(step);
+                let second_correction = 1.0 - beta2.powi(step);
+                let mut delta = Matrix::zeros(gradient.rows, gradient.cols);
+                for i in 0..gradient.rows {
+                    for j in 0..gradient.cols {
+                        let m = first.data[i][j] / first_correction;
+                        let v = second.data[i][j] / second_correction;
+                        delta.data[i][j] = learning_rate * m / (v.sqrt() + epsilon);
+                    }
+                }
+                delta
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::activations::{IDENTITY, SIGMOID};
+    use crate::nn::matrix::Matrix;
+    use crate::nn::network::Network;
+    use crate::nn::optimizer::{Optimizer, OptimizerState};
+
+    // Minimizes (x - 3)² from x = 0.
+    fn minimize(optimizer: Optimizer, learning_rate: f64, steps: usize) -> f64 {
+        let mut state = OptimizerState::new(optimizer);
+        let mut x = Matrix::from(vec![vec![0.0]]);
+        for _ in 0..steps {
+            state.next_step();
+            let gradient = x.map(&|x| 2.0 * (x - 3.0));
+            x = x.subtract(&state.update(0, &gradient, learning_rate));
+        }
+        x.data[0][0]
+    }
+
+    #[test]
+    fn optimizers_converge() {
+        assert!((minimize(Optimizer::Sgd, 0.1, 100) - 3.0).abs() < 1e-6);
+        assert!((minimize(Optimizer::momentum(), 0.01, 300) - 3.0).abs() < 1e-3);
+        assert!((minimize(Optimizer::adam(), 0.1, 500) - 3.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn adam_first_step_is_learning_rate() {
+        let mut state = OptimizerState::new(Optimizer::adam());
+        state.next_step();
+        let delta = state.update(0, &Matrix::from(vec![vec![0.5, -20.0]]), 0.01);
+        assert!((delta.data[0][0] - 0.01).abs() < 1e-6);
+        assert!((delta.data[0][1] + 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn network_trains_with_adam() {
+        let inputs = vec![vec![
+            vec![0.0, 0.0],
+            vec![0.0, 1.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+        ]];
+        let targets = vec![vec![vec![0.0], vec![1.0], vec![1.0], vec![0.0]]];
+
+        let mut network = Network::new(vec![(2, SIGMOID), (8, SIGMOID), (1, IDENTITY)], 0.05);
+        network.initialize(3);
+        network.set_optimizer(Optimizer::adam());
+        let first = network.train_one_epoch(&inputs, &targets, 0.05);
+        let mut error = first;
+        for _ in 0..1000 {
+            error = network.train_one_epoch(&inputs, &targets, 0.05);
+        }
+        assert!(error < first / 10.0, "{} -> {}", first, error);
+    }
+}
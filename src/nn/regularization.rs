@@ -0,0 +1,126 @@
+use super::matrix::Matrix;
+
+// Per transform weight regularization. Penalties are added to the gradient
+// and to the reported loss, weight decay is applied separately from the
+// gradient (decoupled) and constraints are enforced after every update.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Regularization {
+    pub l1: f64,
+    pub l2: f64,
+    pub weight_decay: f64,
+    // Maximum L2 norm of the incoming weights of every unit (matrix column).
+    pub max_norm: Option<f64>,
+    pub non_negative: bool,
+}
+
+impl Regularization {
+    pub fn l1(l1: f64) -> Regularization {
+        Regularization {
+            l1,
+            ..Default::default()
+        }
+    }
+
+    pub fn l2(l2: f64) -> Regularization {
+        Regularization {
+            l2,
+            ..Default::default()
+        }
+    }
+
+    pub fn penalty(&self, weights: &Matrix) -> f64 {
+        if self.l1 == 0.0 && self.l2 == 0.0 {
+            return 0.0;
+        }
+
+        let l1 = weights.map(&|x| x.abs()).collect_sum();
+        let l2 = weights.square().collect_sum();
+        self.l1 * l1 + self.l2 * l2
+    }
+
+    pub fn gradient(&self, weights: &Matrix) -> Matrix {
+        let (l1, l2) = (self.l1, self.l2);
+        weights.map(&|x| {
+            let sign = if x > 0.0 {
+                1.0
+            } else if x < 0.0 {
+                -1.0
+            } else {
+                0.0
+            };
+            l1 * sign + 2.0 * l2 * x
+        })
+    }
+
+    pub fn decay(&self, weights: &Matrix, learning_rate: f64) -> Matrix {
+        if self.weight_decay == 0.0 {
+            return weights.clone();
+        }
+
+        let factor = 1.0 - learning_rate * self.weight_decay;
+        weights.map(&|x| x * factor)
+    }
+
+    pub fn constrain(&self, weights: &Matrix) -> Matrix {
+        let mut result = weights.clone();
+
+        if let Some(max_norm) = self.max_norm {
+            for j in 0..result.cols {
+                let norm = (0..result.rows)
+                    .map(|i| result.data[i][j].powi(2))
+                    .sum::<f64>()
+                    .sqrt();
+                if norm > max_norm {
+                    let scale = max_norm / norm;
+                    for row in result.data.iter_mut() {
+                        row[j] *= scale;
+                    }
+                }
+            }
+        }
+
+        if self.non_negative {
+            result = result.map(&|x| x.max(0.0));
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::matrix::Matrix;
+    use crate::nn::regularization::Regularization;
+
+    #[test]
+    fn penalty_and_gradient() {
+        let weights = Matrix::from(vec![vec![1.0, -2.0], vec![0.0, 3.0]]);
+        let regularization = Regularization {
+            l1: 0.1,
+            l2: 0.01,
+            ..Default::default()
+        };
+
+        assert!((regularization.penalty(&weights) - (0.6 + 0.14)).abs() < 1e-12);
+        assert_eq!(
+            regularization.gradient(&weights),
+            Matrix::from(vec![vec![0.1 + 0.02, -0.1 - 0.04], vec![0.0, 0.1 + 0.06]])
+        );
+    }
+
+    #[test]
+    fn constraints() {
+        let weights = Matrix::from(vec![vec![3.0, -0.5], vec![4.0, 0.5]]);
+        let regularization = Regularization {
+            max_norm: Some(1.0),
+            non_negative: true,
+            ..Default::default()
+        };
+
+        let result = regularization.constrain(&weights);
+        assert!((result.data[0][0] - 0.6).abs() < 1e-12);
+        assert!((result.data[1][0] - 0.8).abs() < 1e-12);
+        assert_eq!(result.data[0][1], 0.0);
+        assert_eq!(result.data[1][1], 0.5);
+    }
+}
@@ -0,0 +1,340 @@
+use std::f64::consts::PI;
+
+use super::{callbacks::Mode, trainer::EpochLog};
+
+// Chooses the learning rate of every epoch (numbered from 1) before it starts.
+// `base_learning_rate` is the model learning rate when training started and
+// `last_log` the log of the previous epoch, if any.
+pub trait LearningRateScheduler {
+    fn learning_rate(
+        &mut self,
+        epoch: usize,
+        base_learning_rate: f64,
+        last_log: Option<&EpochLog>,
+    ) -> f64;
+}
+
+// Multiplies the learning rate by `gamma` every `step_size` epochs.
+pub struct StepDecay {
+    pub step_size: usize,
+    pub gamma: f64,
+}
+
+impl StepDecay {
+    pub fn new(step_size: usize, gamma: f64) -> StepDecay {
+        if step_size == 0 {
+            panic!("Step size must be greater than zero");
+        }
+        StepDecay { step_size, gamma }
+    }
+}
+
+impl LearningRateScheduler for StepDecay {
+    fn learning_rate(&mut self, epoch: usize, base: f64, _last_log: Option<&EpochLog>) -> f64 {
+        base * self.gamma.powi(((epoch - 1) / self.step_size) as i32)
+    }
+}
+
+pub struct ExponentialDecay {
+    pub gamma: f64,
+}
+
+impl ExponentialDecay {
+    pub fn new(gamma: f64) -> ExponentialDecay {
+        ExponentialDecay { gamma }
+    }
+}
+
+impl LearningRateScheduler for ExponentialDecay {
+    fn learning_rate(&mut self, epoch: usize, base: f64, _last_log: Option<&EpochLog>) -> f64 {
+        base * self.gamma.powi((epoch - 1) as i32)
+    }
+}
+
+// SGDR: cosine annealing from the base rate to `min_learning_rate` over
+// `period` epochs, the period is multiplied by `period_mult` after each restart.
+pub struct CosineAnnealingWarmRestarts {
+    pub period: usize,
+    pub period_mult: usize,
+    pub min_learning_rate: f64,
+}
+
+impl CosineAnnealingWarmRestarts {
+    pub fn new(
+        period: usize,
+        period_mult: usize,
+        min_learning_rate: f64,
+    ) -> CosineAnnealingWarmRestarts {
+        if period == 0 || period_mult == 0 {
+            panic!("Period and period multiplier must be greater than zero");
+        }
+        CosineAnnealingWarmRestarts {
+            period,
+            period_mult,
+            min_learning_rate,
+        }
+    }
+}
+
+impl LearningRateScheduler for CosineAnnealingWarmRestarts {
+    fn learning_rate(&mut self, epoch: usize, base: f64, _last_log: Option<&EpochLog>) -> f64 {
+        let mut position = epoch - 1;
+        let mut period = self.period;
+        while position >= period {
+            position -= period;
+            period *= self.period_mult;
+        }
+
+        let progress = position as f64 / period as f64;
+        self.min_learning_rate
+            + (base - self.min_learning_rate) * (1.0 + (PI * progress).cos()) / 2.0
+    }
+}
+
+// Grows the learning rate linearly to the base rate during `warmup_epochs`,
+// then hands over to the wrapped scheduler (with epochs counted from the end
+// of the warmup) or keeps the base rate.
+pub struct LinearWarmup {
+    pub warmup_epochs: usize,
+    after: Option<Box<dyn LearningRateScheduler>>,
+}
+
+impl LinearWarmup {
+    pub fn new(warmup_epochs: usize) -> LinearWarmup {
+        LinearWarmup {
+            warmup_epochs,
+            after: None,
+        }
+    }
+
+    pub fn then(mut self, scheduler: impl LearningRateScheduler + 'static) -> LinearWarmup {
+        self.after = Some(Box::new(scheduler));
+        self
+    }
+}
+
+impl LearningRateScheduler for LinearWarmup {
+    fn learning_rate(&mut self, epoch: usize, base: f64, last_log: Option<&EpochLog>) -> f64 {
+        if epoch <= self.warmup_epochs {
+            return base * epoch as f64 / self.warmup_epochs as f64;
+        }
+
+        match self.after.as_mut() {
+            Some(scheduler) => scheduler.learning_rate(epoch - self.warmup_epochs, base, last_log),
+            None => base,
+        }
+    }
+}
+
+// The 1cycle policy: cosine warmup from max / div_factor to max during the
+// first `pct_start` of the training, then cosine annealing down to
+// max / (div_factor * final_div_factor). Ignores the base learning rate.
+pub struct OneCycle {
+    pub max_learning_rate: f64,
+    pub total_epochs: usize,
+    pub pct_start: f64,
+    pub div_factor: f64,
+    pub final_div_factor: f64,
+}
+
+impl OneCycle {
+    pub fn new(max_learning_rate: f64, total_epochs: usize) -> OneCycle {
+        if total_epochs < 2 {
+            panic!("OneCycle needs at least two epochs");
+        }
+        OneCycle {
+            max_learning_rate,
+            total_epochs,
+            pct_start: 0.3,
+            div_factor: 25.0,
+            final_div_factor: 1e4,
+        }
+    }
+}
+
+fn cosine_interpolation(from: f64, to: f64, progress: f64) -> f64 {
+    to + (from - to) * (1.0 + (PI * progress.clamp(0.0, 1.0)).cos()) / 2.0
+}
+
+impl LearningRateScheduler for OneCycle {
+    fn learning_rate(&mut self, epoch: usize, _base: f64, _last_log: Option<&EpochLog>) -> f64 {
+        let initial = self.max_learning_rate / self.div_factor;
+        let last = initial / self.final_div_factor;
+        let step = (epoch - 1) as f64;
+        let total = (self.total_epochs - 1) as f64;
+        let peak = (self.pct_start * total).round().max(1.0);
+
+        if step <= peak {
+            cosine_interpolation(initial, self.max_learning_rate, step / peak)
+        } else {
+            cosine_interpolation(self.max_learning_rate, last, (step - peak) / (total - peak))
+        }
+    }
+}
+
+// Multiplies the learning rate by `factor` once the monitored value of the
+// epoch log has not improved for `patience` epochs.
+pub struct ReduceOnPlateau {
+    pub monitor: String,
+    pub mode: Mode,
+    pub factor: f64,
+    pub patience: usize,
+    pub min_learning_rate: f64,
+    current: Option<f64>,
+    best: Option<f64>,
+    wait: usize,
+}
+
+impl ReduceOnPlateau {
+    pub fn new(monitor: &str, factor: f64, patience: usize) -> ReduceOnPlateau {
+        if !(0.0..1.0).contains(&factor) {
+            panic!("Factor must be in [0, 1), got {}", factor);
+        }
+        ReduceOnPlateau {
+            monitor: monitor.to_string(),
+            mode: Mode::Min,
+            factor,
+            patience,
+            min_learning_rate: 0.0,
+            current: None,
+            best: None,
+            wait: 0,
+        }
+    }
+
+    pub fn mode(mut self, mode: Mode) -> ReduceOnPlateau {
+        self.mode = mode;
+        self
+    }
+
+    pub fn min_learning_rate(mut self, min_learning_rate: f64) -> ReduceOnPlateau {
+        self.min_learning_rate = min_learning_rate;
+        self
+    }
+}
+
+impl LearningRateScheduler for ReduceOnPlateau {
+    fn learning_rate(&mut self, epoch: usize, base: f64, last_log: Option<&EpochLog>) -> f64 {
+        if epoch == 1 {
+            self.current = Some(base);
+            self.best = None;
+            self.wait = 0;
+        }
+        let current = self.current.unwrap_or(base);
+
+        let value = match last_log.and_then(|log| log.value(&self.monitor)) {
+            Some(value) => value,
+            None => return current,
+        };
+
+        if self.mode.improved(value, self.best, 0.0) {
+            self.best = Some(value);
+            self.wait = 0;
+        } else {
+            self.wait += 1;
+            if self.wait > self.patience {
+                self.current = Some((current * self.factor).max(self.min_learning_rate));
+                self.wait = 0;
+            }
+        }
+
+        self.current.unwrap_or(base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::activations::IDENTITY;
+    use crate::nn::network::Network;
+    use crate::nn::schedulers::{
+        CosineAnnealingWarmRestarts, ExponentialDecay, LearningRateScheduler, LinearWarmup,
+        OneCycle, ReduceOnPlateau, StepDecay,
+    };
+    use crate::nn::trainer::{EpochLog, Trainer};
+
+    fn rates(scheduler: &mut dyn LearningRateScheduler, epochs: usize) -> Vec<f64> {
+        (1..=epochs)
+            .map(|epoch| scheduler.learning_rate(epoch, 1.0, None))
+            .collect()
+    }
+
+    fn assert_close(left: &[f64], right: &[f64]) {
+        assert_eq!(left.len(), right.len());
+        for (l, r) in left.iter().zip(right.iter()) {
+            assert!((l - r).abs() < 1e-9, "{:?} != {:?}", left, right);
+        }
+    }
+
+    #[test]
+    fn decays() {
+        assert_close(
+            &rates(&mut StepDecay::new(2, 0.5), 5),
+            &[1.0, 1.0, 0.5, 0.5, 0.25],
+        );
+        assert_close(
+            &rates(&mut ExponentialDecay::new(0.5), 3),
+            &[1.0, 0.5, 0.25],
+        );
+    }
+
+    #[test]
+    fn cosine_restarts_and_warmup() {
+        assert_close(
+            &rates(&mut CosineAnnealingWarmRestarts::new(2, 2, 0.0), 7),
+            &[
+                1.0,
+                0.5,
+                1.0,
+                0.853_553_390_593_273_8,
+                0.5,
+                0.146_446_609_406_726_2,
+                1.0,
+            ],
+        );
+        assert_close(
+            &rates(&mut LinearWarmup::new(4).then(StepDecay::new(1, 0.5)), 6),
+            &[0.25, 0.5, 0.75, 1.0, 1.0, 0.5],
+        );
+    }
+
+    #[test]
+    fn one_cycle_peaks_and_anneals() {
+        let rates = rates(&mut OneCycle::new(1.0, 11), 11);
+        assert!((rates[0] - 0.04).abs() < 1e-9);
+        assert!((rates[3] - 1.0).abs() < 1e-9);
+        assert!((rates[10] - 0.04 / 1e4).abs() < 1e-12);
+        assert!(rates[..4].windows(2).all(|w| w[0] < w[1]));
+        assert!(rates[3..].windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn reduce_on_plateau() {
+        let mut scheduler = ReduceOnPlateau::new("loss", 0.1, 1);
+        let log = |loss| EpochLog {
+            train_loss: loss,
+            ..Default::default()
+        };
+
+        assert_eq!(scheduler.learning_rate(1, 1.0, None), 1.0);
+        assert_eq!(scheduler.learning_rate(2, 1.0, Some(&log(0.5))), 1.0);
+        assert_eq!(scheduler.learning_rate(3, 1.0, Some(&log(0.6))), 1.0);
+        assert!((scheduler.learning_rate(4, 1.0, Some(&log(0.6))) - 0.1).abs() < 1e-12);
+        assert!((scheduler.learning_rate(5, 1.0, Some(&log(0.4))) - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn trainer_records_learning_rate() {
+        let inputs = vec![vec![0.0], vec![1.0]];
+        let targets = vec![vec![0.0], vec![1.0]];
+        let mut network = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.1);
+
+        let history = Trainer::new(3, 2).scheduler(StepDecay::new(1, 0.5)).fit(
+            &mut network,
+            &inputs,
+            &targets,
+        );
+
+        assert_close(&history.learning_rate(), &[0.1, 0.05, 0.025]);
+        assert_eq!(network.learning_rate, 0.1);
+    }
+}
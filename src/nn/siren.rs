@@ -0,0 +1,149 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{activations::IDENTITY, layer::Layer, matrix::Matrix, network::Network};
+
+// Frequency factor used by the SIREN paper.
+pub const OMEGA_0: f64 = 30.0;
+
+// sin(omega_0 * x). A layer rather than an `Activation` because its
+// derivative can't be recovered from the output alone.
+pub struct Sine {
+    pub omega_0: f64,
+    input: Option<Matrix>,
+}
+
+impl Sine {
+    pub fn new(omega_0: f64) -> Sine {
+        Sine {
+            omega_0,
+            input: None,
+        }
+    }
+}
+
+impl Layer for Sine {
+    fn forward(&mut self, input: &Matrix, _training: bool) -> Matrix {
+        let omega_0 = self.omega_0;
+        self.input = Some(input.clone());
+        input.map(&|x| (omega_0 * x).sin())
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> Matrix {
+        let omega_0 = self.omega_0;
+        let input = self
+            .input
+            .as_ref()
+            .expect("Sine::backward called before forward");
+        gradient.scalar_multiplication(&input.map(&|x| omega_0 * (omega_0 * x).cos()))
+    }
+}
+
+fn uniform(rng: &mut StdRng, rows: usize, cols: usize, limit: f64) -> Matrix {
+    let mut matrix = Matrix::zeros(rows, cols);
+    for row in matrix.data.iter_mut() {
+        for value in row.iter_mut() {
+            *value = rng.gen_range(-limit..=limit);
+        }
+    }
+    matrix
+}
+
+// SIREN initialization: the first transform is drawn from U(-1/n, 1/n) and
+// the others from U(-sqrt(6/n)/omega_0, sqrt(6/n)/omega_0), n being the
+// transform input size, which keeps the activations of deep sine networks
+// well distributed. Biases use U(-1/sqrt(n), 1/sqrt(n)).
+pub fn siren_initialize(network: &mut Network, omega_0: f64, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for i in 0..network.weights.len() {
+        let (inputs, outputs) = (network.weights[i].rows, network.weights[i].cols);
+        let n = inputs as f64;
+        let limit = if i == 0 {
+            1.0 / n
+        } else {
+            (6.0 / n).sqrt() / omega_0
+        };
+        network.weights[i] = uniform(&mut rng, inputs, outputs, limit);
+        network.biases[i] = uniform(&mut rng, 1, outputs, 1.0 / n.sqrt());
+    }
+}
+
+// A SIREN: linear transforms between the given sizes with sine layers after
+// every hidden one and a linear output, initialized with `siren_initialize`.
+// Suits targets normalized to [-1, 1].
+pub fn siren(sizes: &[usize], learning_rate: f64, omega_0: f64, seed: u64) -> Network<'static> {
+    if sizes.len() < 2 {
+        panic!("A SIREN needs at least an input and an output size");
+    }
+
+    let mut network = Network::new(
+        sizes.iter().map(|&size| (size, IDENTITY)).collect(),
+        learning_rate,
+    );
+    for i in 0..sizes.len() - 2 {
+        network.add_layer(i, Box::new(Sine::new(omega_0)));
+    }
+    siren_initialize(&mut network, omega_0, seed);
+    network
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::layer::Layer;
+    use crate::nn::matrix::Matrix;
+    use crate::nn::siren::{siren, Sine, OMEGA_0};
+
+    #[test]
+    fn sine_gradient_matches_finite_differences() {
+        let mut sine = Sine::new(2.0);
+        let input = Matrix::from(vec![vec![0.3, -1.2]]);
+        sine.forward(&input, true);
+        let gradient = sine.backward(&Matrix::from(vec![vec![1.0, 1.0]]));
+
+        let h = 1e-6;
+        for j in 0..2 {
+            let numeric = ((2.0 * (input.data[0][j] + h)).sin()
+                - (2.0 * (input.data[0][j] - h)).sin())
+                / (2.0 * h);
+            assert!((gradient.data[0][j] - numeric).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn siren_is_seeded_and_bounded() {
+        let network = siren(&[2, 16, 16, 3], 0.01, OMEGA_0, 7);
+        let again = siren(&[2, 16, 16, 3], 0.01, OMEGA_0, 7);
+        assert_eq!(network.weights, again.weights);
+
+        assert!(network.weights[0]
+            .data
+            .iter()
+            .flatten()
+            .all(|w| w.abs() <= 0.5));
+        let limit = (6.0f64 / 16.0).sqrt() / OMEGA_0;
+        assert!(network.weights[1]
+            .data
+            .iter()
+            .flatten()
+            .all(|w| w.abs() <= limit));
+    }
+
+    #[test]
+    fn siren_fits_a_sine_wave() {
+        let inputs: Vec<Vec<f64>> = (0..32).map(|i| vec![i as f64 / 32.0]).collect();
+        let targets: Vec<Vec<f64>> = inputs
+            .iter()
+            .map(|x| vec![(6.0 * std::f64::consts::PI * x[0]).sin()])
+            .collect();
+
+        let (inputs, targets) = ([inputs], [targets]);
+
+        let mut network = siren(&[1, 32, 1], 0.0005, OMEGA_0, 1);
+        let mut error = network.train_one_epoch(&inputs, &targets, 0.0005);
+        let first = error;
+        for _ in 0..300 {
+            error = network.train_one_epoch(&inputs, &targets, 0.0005);
+        }
+        assert!(error < first / 10.0, "{} -> {}", first, error);
+    }
+}
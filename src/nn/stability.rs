@@ -0,0 +1,132 @@
+use std::fmt::{Display, Formatter, Result};
+
+use super::matrix::Matrix;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientClipping {
+    // Clamps every gradient component to [-limit, limit].
+    Value(f64),
+    // Rescales all gradients together when their global L2 norm exceeds the limit.
+    Norm(f64),
+}
+
+impl GradientClipping {
+    pub fn clip(&self, gradients: Vec<Matrix>) -> Vec<Matrix> {
+        match *self {
+            GradientClipping::Value(limit) => gradients
+                .iter()
+                .map(|gradient| gradient.map(&|x| x.clamp(-limit, limit)))
+                .collect(),
+            GradientClipping::Norm(max_norm) => {
+                let norm = global_norm(&gradients);
+                if !norm.is_finite() || norm <= max_norm {
+                    return gradients;
+                }
+                let scale = max_norm / norm;
+                gradients
+                    .iter()
+                    .map(|gradient| gradient.map(&|x| x * scale))
+                    .collect()
+            }
+        }
+    }
+}
+
+pub fn global_norm(matrices: &[Matrix]) -> f64 {
+    matrices
+        .iter()
+        .map(|matrix| matrix.square().collect_sum())
+        .sum::<f64>()
+        .sqrt()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage {
+    Activations,
+    Gradients,
+    Weights,
+    Biases,
+    // Parameters of the layers added with `Network::add_layer`.
+    Layers,
+}
+
+// First NaN / infinite value seen by a network with numerics checks enabled.
+// `transform` is the index of the transform (layers[i] -> layers[i + 1]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NonFinite {
+    pub stage: Stage,
+    pub transform: usize,
+    pub value: f64,
+}
+
+impl Display for NonFinite {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let stage = match self.stage {
+            Stage::Activations => "activations",
+            Stage::Gradients => "gradients",
+            Stage::Weights => "weights",
+            Stage::Biases => "biases",
+            Stage::Layers => "layer parameters",
+        };
+        write!(
+            f,
+            "{} found in {} of transform {} (layer {} -> {})",
+            self.value,
+            stage,
+            self.transform,
+            self.transform,
+            self.transform + 1
+        )
+    }
+}
+
+pub fn find_non_finite(matrix: &Matrix, stage: Stage, transform: usize) -> Option<NonFinite> {
+    matrix
+        .data
+        .iter()
+        .flatten()
+        .find(|value| !value.is_finite())
+        .map(|&value| NonFinite {
+            stage,
+            transform,
+            value,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::matrix::Matrix;
+    use crate::nn::stability::{find_non_finite, GradientClipping, Stage};
+
+    #[test]
+    fn clip_by_value_and_norm() {
+        let gradients = vec![
+            Matrix::from(vec![vec![3.0, -5.0]]),
+            Matrix::from(vec![vec![0.5]]),
+        ];
+
+        let clipped = GradientClipping::Value(1.0).clip(gradients.clone());
+        assert_eq!(clipped[0], Matrix::from(vec![vec![1.0, -1.0]]));
+        assert_eq!(clipped[1], Matrix::from(vec![vec![0.5]]));
+
+        let gradients = vec![
+            Matrix::from(vec![vec![3.0, 0.0]]),
+            Matrix::from(vec![vec![4.0]]),
+        ];
+        let clipped = GradientClipping::Norm(1.0).clip(gradients);
+        assert!((clipped[0].data[0][0] - 0.6).abs() < 1e-12);
+        assert!((clipped[1].data[0][0] - 0.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reports_non_finite_values() {
+        let matrix = Matrix::from(vec![vec![1.0, f64::NAN]]);
+        let report = find_non_finite(&matrix, Stage::Gradients, 2).unwrap();
+        assert_eq!(report.transform, 2);
+        assert_eq!(
+            report.to_string(),
+            "NaN found in gradients of transform 2 (layer 2 -> 3)"
+        );
+        assert!(find_non_finite(&Matrix::zeros(2, 2), Stage::Weights, 0).is_none());
+    }
+}
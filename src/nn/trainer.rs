@@ -0,0 +1,379 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use super::{
+    callbacks::{Callback, CallbackAction},
+    model::Model,
+    schedulers::LearningRateScheduler,
+};
+use crate::data::{dataset::InMemoryDataset, loader::DataLoader};
+
+// Computes a score from a batch of model outputs and the matching targets.
+pub type Metric = Box<dyn Fn(&[Vec<f64>], &[Vec<f64>]) -> f64>;
+
+type Samples = (Vec<Vec<f64>>, Vec<Vec<f64>>);
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EpochLog {
+    pub epoch: usize,
+    pub learning_rate: f64,
+    pub train_loss: f64,
+    pub validation_loss: Option<f64>,
+    pub train_metrics: BTreeMap<String, f64>,
+    pub validation_metrics: BTreeMap<String, f64>,
+}
+
+impl EpochLog {
+    // Looks up a monitored value: `loss`, `val_loss`, a metric name or
+    // `val_` followed by a metric name.
+    pub fn value(&self, name: &str) -> Option<f64> {
+        match name {
+            "loss" => Some(self.train_loss),
+            "val_loss" => self.validation_loss,
+            _ => match name.strip_prefix("val_") {
+                Some(metric) => self.validation_metrics.get(metric).copied(),
+                None => self.train_metrics.get(name).copied(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct History {
+    pub epochs: Vec<EpochLog>,
+}
+
+impl History {
+    pub fn train_loss(&self) -> Vec<f64> {
+        self.epochs.iter().map(|log| log.train_loss).collect()
+    }
+
+    pub fn learning_rate(&self) -> Vec<f64> {
+        self.epochs.iter().map(|log| log.learning_rate).collect()
+    }
+
+    pub fn validation_loss(&self) -> Vec<f64> {
+        self.epochs
+            .iter()
+            .filter_map(|log| log.validation_loss)
+            .collect()
+    }
+
+    pub fn train_metric(&self, name: &str) -> Vec<f64> {
+        self.epochs
+            .iter()
+            .filter_map(|log| log.train_metrics.get(name).copied())
+            .collect()
+    }
+
+    pub fn validation_metric(&self, name: &str) -> Vec<f64> {
+        self.epochs
+            .iter()
+            .filter_map(|log| log.validation_metrics.get(name).copied())
+            .collect()
+    }
+}
+
+pub struct Trainer {
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub shuffle_seed: Option<u64>,
+    validation: Option<Samples>,
+    metrics: Vec<(String, Metric)>,
+    callbacks: Vec<Box<dyn Callback>>,
+    scheduler: Option<Box<dyn LearningRateScheduler>>,
+}
+
+impl Trainer {
+    pub fn new(epochs: usize, batch_size: usize) -> Trainer {
+        if batch_size == 0 {
+            panic!("Batch size must be greater than zero");
+        }
+
+        Trainer {
+            epochs,
+            batch_size,
+            shuffle_seed: None,
+            validation: None,
+            metrics: vec![],
+            callbacks: vec![],
+            scheduler: None,
+        }
+    }
+
+    pub fn shuffle(mut self, seed: u64) -> Trainer {
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    pub fn validation(mut self, inputs: Vec<Vec<f64>>, targets: Vec<Vec<f64>>) -> Trainer {
+        if inputs.len() != targets.len() {
+            panic!(
+                "Validation set has {} inputs and {} targets",
+                inputs.len(),
+                targets.len()
+            );
+        }
+        self.validation = Some((inputs, targets));
+        self
+    }
+
+    pub fn metric(
+        mut self,
+        name: &str,
+        metric: impl Fn(&[Vec<f64>], &[Vec<f64>]) -> f64 + 'static,
+    ) -> Trainer {
+        self.metrics.push((name.to_string(), Box::new(metric)));
+        self
+    }
+
+    pub fn callback(mut self, callback: impl Callback + 'static) -> Trainer {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    // Sets the learning rate of every epoch, the model's own one is restored
+    // once `fit` returns.
+    pub fn scheduler(mut self, scheduler: impl LearningRateScheduler + 'static) -> Trainer {
+        self.scheduler = Some(Box::new(scheduler));
+        self
+    }
+
+    pub fn fit<M: Model>(
+        &mut self,
+        model: &mut M,
+        inputs: &[Vec<f64>],
+        targets: &[Vec<f64>],
+    ) -> History {
+        if inputs.len() != targets.len() {
+            panic!(
+                "Training set has {} inputs and {} targets",
+                inputs.len(),
+                targets.len()
+            );
+        }
+        if inputs.is_empty() {
+            panic!("Training set is empty");
+        }
+
+        let dataset = InMemoryDataset::new(inputs.to_vec(), targets.to_vec());
+        let mut loader = DataLoader::new(Arc::new(dataset), self.batch_size);
+        if let Some(seed) = self.shuffle_seed {
+            loader = loader.shuffle(seed);
+        }
+
+        self.fit_loader(model, &mut loader)
+    }
+
+    // Trains on the batches of a data loader, its batch size and shuffling
+    // take precedence over the trainer ones.
+    pub fn fit_loader<M: Model>(&mut self, model: &mut M, loader: &mut DataLoader) -> History {
+        if loader.is_empty() {
+            panic!("Training set is empty");
+        }
+
+        let mut history = History::default();
+        let base_learning_rate = model.learning_rate();
+
+        for callback in self.callbacks.iter_mut() {
+            callback.on_train_begin(model);
+        }
+
+        for epoch in 1..=self.epochs {
+            if let Some(scheduler) = self.scheduler.as_mut() {
+                let learning_rate =
+                    scheduler.learning_rate(epoch, base_learning_rate, history.epochs.last());
+                model.set_learning_rate(learning_rate);
+            }
+
+            model.train_mode();
+            for callback in self.callbacks.iter_mut() {
+                callback.on_epoch_begin(epoch, model);
+            }
+
+            let learning_rate = model.learning_rate();
+            let mut error = 0.0;
+            let mut samples = 0;
+            let mut stop = false;
+            let mut epoch_outputs = Vec::with_capacity(loader.samples());
+            let mut epoch_targets = Vec::with_capacity(loader.samples());
+
+            for (batch_index, (batch_inputs, batch_targets)) in loader.batches().enumerate() {
+                for callback in self.callbacks.iter_mut() {
+                    callback.on_batch_begin(batch_index);
+                }
+
+                let batch_size = batch_inputs.rows;
+                let batch_targets = batch_targets.data;
+                let outputs = model.feed_forward(batch_inputs.data);
+                let batch_error = model.calculate_error(&outputs, &batch_targets);
+                error += batch_error * batch_size as f64;
+                samples += batch_size;
+
+                if !self.metrics.is_empty() {
+                    epoch_outputs.extend(outputs.iter().cloned());
+                    epoch_targets.extend(batch_targets.iter().cloned());
+                }
+
+                model.back_propagate(outputs, batch_targets, learning_rate);
+
+                for callback in self.callbacks.iter_mut() {
+                    stop |= callback.on_batch_end(batch_index, batch_error) == CallbackAction::Stop;
+                }
+                if stop {
+                    break;
+                }
+            }
+
+            let mut log = EpochLog {
+                epoch,
+                learning_rate,
+                train_loss: error / samples as f64 + model.regularization_loss(),
+                train_metrics: self.compute_metrics(&epoch_outputs, &epoch_targets),
+                ..Default::default()
+            };
+
+            if let Some((validation_inputs, validation_targets)) = &self.validation {
+                let (loss, metrics) = self.evaluate(model, validation_inputs, validation_targets);
+                log.validation_loss = Some(loss);
+                log.validation_metrics = metrics;
+            }
+
+            for callback in self.callbacks.iter_mut() {
+                stop |= callback.on_epoch_end(&log, model) == CallbackAction::Stop;
+            }
+
+            history.epochs.push(log);
+            if stop {
+                break;
+            }
+        }
+
+        // A scheduler only changes the learning rate during this fit.
+        model.set_learning_rate(base_learning_rate);
+
+        for callback in self.callbacks.iter_mut() {
+            callback.on_train_end(model);
+        }
+
+        history
+    }
+
+    // Loss and metrics of the model in eval mode. The previous mode is restored.
+    pub fn evaluate<M: Model + ?Sized>(
+        &self,
+        model: &mut M,
+        inputs: &[Vec<f64>],
+        targets: &[Vec<f64>],
+    ) -> (f64, BTreeMap<String, f64>) {
+        if inputs.is_empty() {
+            return (0.0, BTreeMap::new());
+        }
+
+        let training = model.is_training();
+        model.eval_mode();
+
+        let mut error = 0.0;
+        let mut outputs = Vec::with_capacity(inputs.len());
+        for (batch_inputs, batch_targets) in inputs
+            .chunks(self.batch_size)
+            .zip(targets.chunks(self.batch_size))
+        {
+            let batch_outputs = model.feed_forward(batch_inputs.to_vec());
+            error +=
+                model.calculate_error(&batch_outputs, batch_targets) * batch_inputs.len() as f64;
+            outputs.extend(batch_outputs);
+        }
+
+        if training {
+            model.train_mode();
+        }
+
+        (
+            error / inputs.len() as f64,
+            self.compute_metrics(&outputs, targets),
+        )
+    }
+
+    fn compute_metrics(&self, outputs: &[Vec<f64>], targets: &[Vec<f64>]) -> BTreeMap<String, f64> {
+        if outputs.is_empty() {
+            return BTreeMap::new();
+        }
+
+        self.metrics
+            .iter()
+            .map(|(name, metric)| (name.clone(), metric(outputs, targets)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::activations::IDENTITY;
+    use crate::nn::network::Network;
+    use crate::nn::trainer::Trainer;
+
+    fn dataset() -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+        let inputs: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64 / 20.0]).collect();
+        let targets = inputs.iter().map(|x| vec![2.0 * x[0] + 1.0]).collect();
+        (inputs, targets)
+    }
+
+    #[test]
+    fn fit_records_history() {
+        let (inputs, targets) = dataset();
+        let mut network = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.05);
+        let mut trainer = Trainer::new(50, 4)
+            .shuffle(3)
+            .validation(inputs.clone(), targets.clone())
+            .metric("max_error", |outputs, targets| {
+                outputs
+                    .iter()
+                    .zip(targets.iter())
+                    .map(|(o, t)| (o[0] - t[0]).abs())
+                    .fold(0.0, f64::max)
+            });
+
+        let history = trainer.fit(&mut network, &inputs, &targets);
+
+        assert_eq!(history.epochs.len(), 50);
+        assert_eq!(history.validation_loss().len(), 50);
+        assert_eq!(history.train_metric("max_error").len(), 50);
+        assert_eq!(history.validation_metric("max_error").len(), 50);
+
+        let losses = history.validation_loss();
+        assert!(losses[49] < losses[0]);
+        assert!(losses[49] < 1e-3);
+    }
+
+    #[test]
+    fn shuffle_is_reproducible() {
+        let (inputs, targets) = dataset();
+        let mut first = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.05);
+        let mut second = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.05);
+        second.weights = first.weights.clone();
+        second.biases = first.biases.clone();
+
+        let first_history = Trainer::new(5, 3)
+            .shuffle(9)
+            .fit(&mut first, &inputs, &targets);
+        let second_history = Trainer::new(5, 3)
+            .shuffle(9)
+            .fit(&mut second, &inputs, &targets);
+        assert_eq!(first_history, second_history);
+    }
+
+    #[test]
+    fn evaluate_keeps_the_model_mode() {
+        let (inputs, targets) = dataset();
+        let mut network = Network::new(vec![(1, IDENTITY), (1, IDENTITY)], 0.05);
+        let trainer = Trainer::new(1, 4);
+
+        network.eval_mode();
+        trainer.evaluate(&mut network, &inputs, &targets);
+        assert!(!network.is_training());
+
+        network.train_mode();
+        trainer.evaluate(&mut network, &inputs, &targets);
+        assert!(network.is_training());
+    }
+}
@@ -1,14 +1,5 @@
-pub mod nn {
-    pub mod activations;
-    pub mod matrix;
-    pub mod network;
-}
-mod image_nn;
-
-use nn::activations::SIGMOID;
-use nn::network::Network;
-
-use crate::nn::activations::{Activation, IDENTITY, RELU};
+use rust_nn::nn::activations::{Activation, IDENTITY};
+use rust_nn::nn::network::Network;
 
 fn main() {
     let inputs: Vec<Vec<Vec<f64>>> = vec![